@@ -4,11 +4,13 @@ pub use library::*;
 pub use module::*;
 pub use program::*;
 pub use reloc::*;
+pub use section::*;
 pub use symbol::*;
 pub use ty::*;
 
 use bitflags::bitflags;
 use byteorder::{ByteOrder, LE};
+use flate2::{Decompress, FlushDecompress, Status};
 use std::io::{Read, Seek, SeekFrom};
 use std::ops::Range;
 use thiserror::Error;
@@ -19,12 +21,20 @@ mod library;
 mod module;
 mod program;
 mod reloc;
+mod section;
 mod symbol;
 mod ty;
 
 /// The first 8 bytes of SELF file.
 pub const SELF_MAGIC: [u8; 8] = [0x4f, 0x15, 0x3d, 0x1d, 0x00, 0x01, 0x01, 0x12];
 
+/// The maximum decompressed size of SELF segment. This is the same as the maximum memory size of
+/// the mappable programs.
+const MAX_SEGMENT_SIZE: u64 = 0x7fffffff;
+
+/// The maximum size of the ELF produced by [`Elf::to_elf()`].
+const MAX_ELF_SIZE: u64 = 0xffffffff;
+
 /// Represents a SELF or ELF file.
 ///
 /// The reason we need to support both SELF and ELF is because every SELF decryptors output ELF.
@@ -32,10 +42,13 @@ pub const SELF_MAGIC: [u8; 8] = [0x4f, 0x15, 0x3d, 0x1d, 0x00, 0x01, 0x01, 0x12]
 pub struct Elf<I: Read + Seek> {
     name: String,
     image: I,
+    image_len: u64,
     self_data: Option<SelfData>,
     ty: FileType,
     entry_addr: Option<usize>,
     programs: Vec<Program>,
+    sections: Vec<Section>,
+    section_error: Option<LoadSectionsError>,
     mapping: Range<usize>,
     code: Option<usize>,
     relro: Option<usize>,
@@ -53,6 +66,12 @@ pub struct Elf<I: Read + Seek> {
 
 impl<I: Read + Seek> Elf<I> {
     pub fn open<N: Into<String>>(name: N, mut image: I) -> Result<Self, OpenError> {
+        // Get image size. We use this to reject the headers that point past the end of the image.
+        let len = match image.seek(SeekFrom::End(0)) {
+            Ok(v) => v,
+            Err(e) => return Err(OpenError::GetImageSizeFailed(e)),
+        };
+
         // Seek to file header.
         if let Err(e) = image.rewind() {
             return Err(OpenError::SeekFailed(0, e));
@@ -67,15 +86,22 @@ impl<I: Read + Seek> Elf<I> {
 
         // Check if image is SELF.
         let (hdr, offset, self_data) = if hdr.starts_with(&SELF_MAGIC) {
-            // Kyty also checking if Category = 0x01 & Program Type = 0x01 & Padding = 0x00.
-            // Let's check only magic for now until something is broken.
+            // Kyty also checking if Category = 0x01 & Program Type = 0x01 & Padding = 0x00. We
+            // expose Category and Program Type via self_category() and self_program_type() but
+            // deliberately do not validate them until something is broken.
             if LE::read_u16(&hdr[0x1a..]) != 0x22 {
                 return Err(OpenError::InvalidSelfMagic);
             }
 
             // Load SELF fields.
+            let category = hdr[0x08];
+            let program_type = hdr[0x09];
             let segment_count = LE::read_u16(&hdr[0x18..]) as usize;
 
+            if 32 + (segment_count as u64) * 32 > len {
+                return Err(OpenError::TooManySelfSegments(segment_count));
+            }
+
             // Seek to the first SELF segment.
             if let Err(e) = image.seek(SeekFrom::Start(32)) {
                 return Err(OpenError::SeekFailed(32, e));
@@ -101,7 +127,11 @@ impl<I: Read + Seek> Elf<I> {
                 });
             }
 
-            let self_data = Some(SelfData { segments });
+            let self_data = Some(SelfData {
+                category,
+                program_type,
+                segments,
+            });
 
             // Get offset for ELF header.
             let elf_offset = match image.stream_position() {
@@ -146,6 +176,10 @@ impl<I: Read + Seek> Elf<I> {
         let e_phoff = offset + 0x40; // PS4 is hard-coded this value.
         let e_phnum: usize = LE::read_u16(&hdr[0x38..]).into();
 
+        if e_phoff + (e_phnum as u64) * 0x38 > len {
+            return Err(OpenError::TooManyPrograms(e_phnum));
+        }
+
         // Seek to first program header.
         match image.seek(SeekFrom::Start(e_phoff)) {
             Ok(v) => {
@@ -167,6 +201,7 @@ impl<I: Read + Seek> Elf<I> {
         let mut elf = Self {
             name: name.into(),
             image,
+            image_len: len,
             self_data,
             ty: e_type,
             entry_addr: match e_entry {
@@ -174,6 +209,8 @@ impl<I: Read + Seek> Elf<I> {
                 v => Some(v as usize),
             },
             programs: Vec::with_capacity(e_phnum),
+            sections: Vec::new(),
+            section_error: None,
             mapping: Range {
                 start: usize::MAX,
                 end: 0,
@@ -220,6 +257,16 @@ impl<I: Read + Seek> Elf<I> {
             elf.programs.push(p);
         }
 
+        // Load section headers. SELF does not contain section headers so we load them only for a
+        // plain ELF. The sections are not required for loading so we keep the error instead of
+        // failing the whole image.
+        if elf.self_data.is_none() {
+            match elf.load_sections(&hdr, len) {
+                Ok(v) => elf.sections = v,
+                Err(e) => elf.section_error = Some(e),
+            }
+        }
+
         // Check mapping range.
         if elf.mapping.start == usize::MAX || elf.mapping.end == 0 {
             return Err(OpenError::NoMappableProgram);
@@ -320,6 +367,58 @@ impl<I: Read + Seek> Elf<I> {
         self.self_data.as_ref().map(|d| d.segments.as_slice())
     }
 
+    /// Returns the category field of the SELF header.
+    pub fn self_category(&self) -> Option<u8> {
+        self.self_data.as_ref().map(|d| d.category)
+    }
+
+    /// Returns the program type field of the SELF header.
+    pub fn self_program_type(&self) -> Option<u8> {
+        self.self_data.as_ref().map(|d| d.program_type)
+    }
+
+    /// Returns `true` if this is a SELF with encrypted segments (a retail SELF). A fake SELF or a
+    /// plain ELF will return `false`.
+    pub fn is_encrypted(&self) -> bool {
+        match &self.self_data {
+            Some(v) => v.segments.iter().any(|s| s.is_encrypted()),
+            None => false,
+        }
+    }
+
+    /// Returns the index of SELF segment that contains the data of program `index`.
+    pub fn self_segment_of(&self, index: usize) -> Option<usize> {
+        self.self_data
+            .as_ref()?
+            .segments
+            .iter()
+            .position(|s| s.is_blocked() && s.flags.program() == index)
+    }
+
+    /// Returns a pair of the program index and the SELF segment index for each mappable program
+    /// (`PT_LOAD` and `PT_SCE_RELRO`).
+    pub fn map_self_segments(&self) -> Result<Vec<(usize, usize)>, MapSelfSegmentsError> {
+        if self.self_data.is_none() {
+            return Err(MapSelfSegmentsError::NotSelf);
+        }
+
+        let mut map = Vec::new();
+
+        for (i, p) in self.programs.iter().enumerate() {
+            match p.ty() {
+                ProgramType::PT_LOAD | ProgramType::PT_SCE_RELRO => {}
+                _ => continue,
+            }
+
+            match self.self_segment_of(i) {
+                Some(s) => map.push((i, s)),
+                None => return Err(MapSelfSegmentsError::NoSegment(i)),
+            }
+        }
+
+        Ok(map)
+    }
+
     pub fn ty(&self) -> FileType {
         self.ty
     }
@@ -336,6 +435,20 @@ impl<I: Read + Seek> Elf<I> {
         self.programs.as_slice()
     }
 
+    pub fn section(&self, i: usize) -> Option<&Section> {
+        self.sections.get(i)
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        self.sections.as_slice()
+    }
+
+    /// Returns the error if the section headers is failed to load. [`Self::sections()`] will be
+    /// empty in this case.
+    pub fn section_error(&self) -> Option<&LoadSectionsError> {
+        self.section_error.as_ref()
+    }
+
     pub fn dynamic(&self) -> Option<usize> {
         self.dynamic
     }
@@ -387,7 +500,34 @@ impl<I: Read + Seek> Elf<I> {
 
         // Get program offset.
         let offset = match &self.self_data {
-            Some(v) => self.get_self_program(v, prog)?,
+            Some(v) => {
+                let (i, offset) = self.get_self_program(v, prog)?;
+                let seg = &v.segments[i];
+
+                if seg.is_compressed() {
+                    // Only decompress the data we need.
+                    let end = offset.saturating_add(len as u64);
+                    let data = match self.read_self_segment_part(i, Some(end)) {
+                        Ok(v) => v,
+                        Err(e) => return Err(ReadProgramError::ReadSelfSegmentFailed(i, e)),
+                    };
+
+                    // The header is not trusted so the program may be outside the data.
+                    let range = match usize::try_from(offset) {
+                        Ok(v) => v.checked_add(len).and_then(|e| data.get(v..e)),
+                        Err(_) => None,
+                    };
+
+                    match range {
+                        Some(v) => buf[..len].copy_from_slice(v),
+                        None => return Err(ReadProgramError::OutOfSegment(i)),
+                    }
+
+                    return Ok(());
+                }
+
+                seg.offset + offset
+            }
             None => prog.offset(),
         };
 
@@ -409,6 +549,294 @@ impl<I: Read + Seek> Elf<I> {
         Ok(())
     }
 
+    /// Reads the whole data of SELF segment `index`. The data will be decompressed if the segment
+    /// is compressed.
+    pub fn read_self_segment(&mut self, index: usize) -> Result<Vec<u8>, ReadSelfSegmentError> {
+        self.read_self_segment_part(index, None)
+    }
+
+    /// Same as [`Self::read_self_segment()`] but stop decompressing after the first `limit` bytes
+    /// if `limit` is specified. `limit` has no effect on the uncompressed segment.
+    fn read_self_segment_part(
+        &mut self,
+        index: usize,
+        limit: Option<u64>,
+    ) -> Result<Vec<u8>, ReadSelfSegmentError> {
+        // Get target segment.
+        let seg = match &self.self_data {
+            Some(v) => match v.segments.get(index) {
+                Some(v) => v,
+                None => return Err(ReadSelfSegmentError::InvalidIndex),
+            },
+            None => return Err(ReadSelfSegmentError::NotSelf),
+        };
+
+        if seg.is_encrypted() {
+            return Err(ReadSelfSegmentError::EncryptedSegment);
+        }
+
+        // Check segment size. Do not allocate the buffer before the sizes are checked because the
+        // header is not trusted.
+        let offset = seg.offset;
+        let compressed = seg.is_compressed();
+        let decompressed_size = seg.decompressed_size;
+
+        match offset.checked_add(seg.compressed_size) {
+            Some(v) if v <= self.image_len => {}
+            _ => {
+                return Err(ReadSelfSegmentError::OutOfImage(
+                    offset,
+                    seg.compressed_size,
+                ))
+            }
+        }
+
+        if compressed {
+            if decompressed_size > MAX_SEGMENT_SIZE {
+                return Err(ReadSelfSegmentError::TooLarge(decompressed_size));
+            }
+        } else if seg.compressed_size != decompressed_size {
+            return Err(ReadSelfSegmentError::InvalidUncompressedSize);
+        }
+
+        let mut data = match seg.compressed_size.try_into() {
+            Ok(v) => vec![0u8; v],
+            Err(_) => return Err(ReadSelfSegmentError::TooLarge(seg.compressed_size)),
+        };
+
+        // Seek to segment data.
+        match self.image.seek(SeekFrom::Start(offset)) {
+            Ok(v) => {
+                if v != offset {
+                    return Err(ReadSelfSegmentError::InvalidOffset(offset));
+                }
+            }
+            Err(e) => return Err(ReadSelfSegmentError::SeekFailed(offset, e)),
+        }
+
+        // Read data.
+        if let Err(e) = self.image.read_exact(&mut data) {
+            return Err(ReadSelfSegmentError::ReadFailed(offset, data.len(), e));
+        }
+
+        if !compressed {
+            return Ok(data);
+        }
+
+        // Decompress. We grow the buffer while decompressing instead of allocating the decompressed
+        // size up front so a small segment cannot make us allocate a huge buffer.
+        let len = limit.map_or(decompressed_size, |v| v.min(decompressed_size));
+        let mut decompressed = Vec::with_capacity(len.min(data.len().max(0x4000) as u64) as usize);
+        let mut deflate = Decompress::new(true);
+        let status = loop {
+            let input = &data[(deflate.total_in() as usize)..];
+            let status =
+                match deflate.decompress_vec(input, &mut decompressed, FlushDecompress::Finish) {
+                    Ok(v) => v,
+                    Err(e) => return Err(ReadSelfSegmentError::DecompressFailed(e)),
+                };
+
+            // Stop if the stream is ended, we got enough data or the input is exhausted before the
+            // buffer is full.
+            if status == Status::StreamEnd
+                || decompressed.len() as u64 >= len
+                || decompressed.len() < decompressed.capacity()
+            {
+                break status;
+            }
+
+            // Grow the buffer but not past the size we need.
+            let additional = decompressed
+                .len()
+                .min((len - decompressed.len() as u64) as usize);
+
+            decompressed.reserve_exact(additional);
+        };
+
+        // Check the decompressed size. The whole stream must end exactly at the size in the header
+        // when we need the whole segment.
+        let total = deflate.total_out();
+
+        if len == decompressed_size {
+            if status != Status::StreamEnd || total != decompressed_size {
+                return Err(ReadSelfSegmentError::InvalidDecompressedSize(total));
+            }
+        } else if total < len {
+            return Err(ReadSelfSegmentError::InvalidDecompressedSize(total));
+        }
+
+        decompressed.truncate(len as usize);
+
+        Ok(decompressed)
+    }
+
+    /// Reconstructs a plain ELF from this SELF. Each program will be placed at its file offset with
+    /// the decompressed data.
+    pub fn to_elf(&mut self) -> Result<Vec<u8>, ToElfError> {
+        // The ELF header is located right after the SELF segment headers.
+        let offset = match &self.self_data {
+            Some(v) => 32 + (v.segments.len() as u64) * 32,
+            None => return Err(ToElfError::NotSelf),
+        };
+
+        if let Err(e) = self.image.seek(SeekFrom::Start(offset)) {
+            return Err(ToElfError::SeekFailed(offset, e));
+        }
+
+        // Read ELF header and program headers.
+        let mut elf = vec![0u8; 0x40 + self.programs.len() * 0x38];
+
+        if let Err(e) = self.image.read_exact(&mut elf) {
+            return Err(ToElfError::ReadHeadersFailed(e));
+        }
+
+        // Clear e_shoff, e_shnum and e_shstrndx because SELF does not contain section headers.
+        LE::write_u64(&mut elf[0x28..], 0);
+        LE::write_u16(&mut elf[0x3c..], 0);
+        LE::write_u16(&mut elf[0x3e..], 0);
+
+        // Get the SELF segment of each program. The mappable programs must have it but the other
+        // programs (e.g. PT_SCE_DYNLIBDATA) may not.
+        let mut segments = match self.map_self_segments() {
+            Ok(v) => v,
+            Err(e) => return Err(ToElfError::MapSegmentsFailed(e)),
+        };
+
+        for i in 0..self.programs.len() {
+            if segments.iter().any(|s| s.0 == i) {
+                continue;
+            }
+
+            if let Some(s) = self.self_segment_of(i) {
+                segments.push((i, s));
+            }
+        }
+
+        // Write programs.
+        for (i, seg) in segments {
+            let data = match self.read_self_segment(seg) {
+                Ok(v) => v,
+                Err(e) => return Err(ToElfError::ReadSegmentFailed(seg, e)),
+            };
+
+            let offset = self.programs[i].offset();
+            let end = match offset.checked_add(data.len() as u64) {
+                Some(v) if v <= MAX_ELF_SIZE => v as usize,
+                _ => return Err(ToElfError::InvalidProgramOffset(i, offset)),
+            };
+
+            let start = offset as usize;
+
+            if elf.len() < end {
+                elf.resize(end, 0);
+            }
+
+            elf[start..end].copy_from_slice(&data);
+        }
+
+        Ok(elf)
+    }
+
+    /// `len` is the size of the image.
+    fn load_sections(
+        &mut self,
+        hdr: &[u8; 64],
+        len: u64,
+    ) -> Result<Vec<Section>, LoadSectionsError> {
+        // Load ELF header.
+        let e_shoff = LE::read_u64(&hdr[0x28..]);
+        let e_shnum: usize = LE::read_u16(&hdr[0x3c..]).into();
+        let e_shstrndx: usize = LE::read_u16(&hdr[0x3e..]).into();
+
+        if e_shnum == 0 {
+            return Ok(Vec::new());
+        } else if LE::read_u16(&hdr[0x3a..]) != 0x40 {
+            return Err(LoadSectionsError::InvalidSectionEntrySize);
+        } else if e_shoff.saturating_add((e_shnum as u64) * 0x40) > len {
+            return Err(LoadSectionsError::TooManySections(e_shnum));
+        }
+
+        // Seek to the first section header.
+        match self.image.seek(SeekFrom::Start(e_shoff)) {
+            Ok(v) => {
+                if v != e_shoff {
+                    return Err(LoadSectionsError::InvalidSectionOffset);
+                }
+            }
+            Err(e) => return Err(LoadSectionsError::SeekFailed(e_shoff, e)),
+        }
+
+        // Read section headers.
+        let mut data = vec![0u8; e_shnum * 0x40];
+
+        if let Err(e) = self.image.read_exact(&mut data) {
+            return Err(LoadSectionsError::ReadSectionHeadersFailed(e));
+        }
+
+        // Read section names.
+        let names = match data.chunks_exact(0x40).nth(e_shstrndx) {
+            Some(h) => {
+                let offset = LE::read_u64(&h[0x18..]);
+                let size = LE::read_u64(&h[0x20..]);
+
+                if offset.saturating_add(size) > len {
+                    return Err(LoadSectionsError::InvalidSectionNames);
+                }
+
+                let mut names = vec![0u8; size.try_into().unwrap()];
+
+                if let Err(e) = self.image.seek(SeekFrom::Start(offset)) {
+                    return Err(LoadSectionsError::SeekFailed(offset, e));
+                }
+
+                if let Err(e) = self.image.read_exact(&mut names) {
+                    return Err(LoadSectionsError::ReadSectionNamesFailed(e));
+                }
+
+                names
+            }
+            None => return Err(LoadSectionsError::InvalidSectionNames),
+        };
+
+        // Load section headers.
+        let mut sections = Vec::with_capacity(e_shnum);
+
+        for (i, h) in data.chunks_exact(0x40).enumerate() {
+            // Lookup name. The null section at index 0 (SHN_UNDEF) has zero sh_name, which is the
+            // empty string at the beginning of the string table. An empty string table means no
+            // section has a name.
+            let name: usize = LE::read_u32(h).try_into().unwrap();
+            let name = if names.is_empty() {
+                String::new()
+            } else {
+                let raw = match names.get(name..) {
+                    Some(v) => v,
+                    None => return Err(LoadSectionsError::InvalidSectionName(i)),
+                };
+
+                match raw.iter().position(|&b| b == 0) {
+                    Some(e) => String::from_utf8_lossy(&raw[..e]).into_owned(),
+                    None => return Err(LoadSectionsError::InvalidSectionName(i)),
+                }
+            };
+
+            sections.push(Section::new(
+                name,
+                SectionType::new(LE::read_u32(&h[0x04..])),
+                SectionFlags::from_bits_retain(LE::read_u64(&h[0x08..])),
+                LE::read_u64(&h[0x10..]) as usize,
+                LE::read_u64(&h[0x18..]),
+                LE::read_u64(&h[0x20..]),
+                LE::read_u32(&h[0x28..]),
+                LE::read_u32(&h[0x2c..]),
+                LE::read_u64(&h[0x30..]),
+                LE::read_u64(&h[0x38..]),
+            ));
+        }
+
+        Ok(sections)
+    }
+
     fn process_mappable(&mut self, index: usize, prog: &Program) -> Result<(), OpenError> {
         // Check offset.
         let ty = prog.ty();
@@ -580,32 +1008,32 @@ impl<I: Read + Seek> Elf<I> {
         Ok(())
     }
 
-    fn get_self_program(&self, data: &SelfData, prog: &Program) -> Result<u64, ReadProgramError> {
+    /// Returns index of the SELF segment that contains `prog` and the offset of `prog` within the
+    /// decompressed data of that segment.
+    fn get_self_program(
+        &self,
+        data: &SelfData,
+        prog: &Program,
+    ) -> Result<(usize, u64), ReadProgramError> {
         // Find the target segment.
         let offset = prog.offset();
         let len = prog.file_size();
 
         for (i, seg) in data.segments.iter().enumerate() {
             // Skip if not blocked segment.
-            let flags = seg.flags;
-
-            if !flags.contains(SelfSegmentFlags::SF_BFLG) {
+            if !seg.is_blocked() {
                 continue;
             }
 
             // Check if the target offset inside the associated program.
-            let prog = &self.programs[flags.program()];
+            let prog = &self.programs[seg.flags.program()];
 
             if offset >= prog.offset() && offset < prog.offset() + prog.file_size() {
                 // Check if segment supported.
-                if flags.contains(SelfSegmentFlags::SF_ENCR) {
+                if seg.is_encrypted() {
                     return Err(ReadProgramError::EncryptedSegment(i));
                 }
 
-                if seg.compressed_size != seg.decompressed_size {
-                    panic!("Compressed SELF segment is not supported yet.");
-                }
-
                 if seg.decompressed_size != prog.file_size() {
                     panic!("SELF segment size different than associated program segment is not supported yet.");
                 }
@@ -617,7 +1045,7 @@ impl<I: Read + Seek> Elf<I> {
                     panic!("Segment block is smaller than the size specified in program header.");
                 }
 
-                return Ok(offset + seg.offset);
+                return Ok((i, offset));
             }
         }
 
@@ -633,6 +1061,8 @@ impl<I: Read + Seek> From<Elf<I>> for (String, Vec<Program>, Option<FileInfo>) {
 
 /// Contains data specific for SELF.
 struct SelfData {
+    category: u8,
+    program_type: u8,
     segments: Vec<SelfSegment>,
 }
 
@@ -660,6 +1090,18 @@ impl SelfSegment {
     pub fn decompressed_size(&self) -> u64 {
         self.decompressed_size
     }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.flags.contains(SelfSegmentFlags::SF_ENCR)
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags.contains(SelfSegmentFlags::SF_DFLG)
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.flags.contains(SelfSegmentFlags::SF_BFLG)
+    }
 }
 
 bitflags! {
@@ -683,6 +1125,9 @@ impl SelfSegmentFlags {
 /// Represents an error for [`Elf::open()`].
 #[derive(Debug, Error)]
 pub enum OpenError {
+    #[error("cannot get size of the image")]
+    GetImageSizeFailed(#[source] std::io::Error),
+
     #[error("cannot seek to offset {0}")]
     SeekFailed(u64, #[source] std::io::Error),
 
@@ -692,6 +1137,9 @@ pub enum OpenError {
     #[error("invalid SELF magic")]
     InvalidSelfMagic,
 
+    #[error("the image is too small for {0} SELF segments")]
+    TooManySelfSegments(usize),
+
     #[error("cannot read a header for SELF segment #{0}")]
     ReadSelfSegmentFailed(usize, #[source] std::io::Error),
 
@@ -716,6 +1164,9 @@ pub enum OpenError {
     #[error("e_phoff is not valid")]
     InvalidProgramOffset,
 
+    #[error("the image is too small for {0} program headers")]
+    TooManyPrograms(usize),
+
     #[error("cannot read program headers")]
     ReadProgramHeadersFailed(#[source] std::io::Error),
 
@@ -774,9 +1225,480 @@ pub enum ReadProgramError {
     #[error("SELF segment #{0} is encrypted")]
     EncryptedSegment(usize),
 
+    #[error("the program is outside SELF segment #{0}")]
+    OutOfSegment(usize),
+
+    #[error("cannot seek to offset {0:#018x}")]
+    SeekFailed(u64, #[source] std::io::Error),
+
+    #[error("cannot read {1} bytes at offset {0:#018x}")]
+    ReadFailed(u64, usize, #[source] std::io::Error),
+
+    #[error("cannot read SELF segment #{0}")]
+    ReadSelfSegmentFailed(usize, #[source] ReadSelfSegmentError),
+}
+
+/// Represents an error for [`Elf::map_self_segments()`].
+#[derive(Debug, Error)]
+pub enum MapSelfSegmentsError {
+    #[error("the image is not a SELF")]
+    NotSelf,
+
+    #[error("program {0} does not have a SELF segment")]
+    NoSegment(usize),
+}
+
+/// Represents an error when the section headers is failed to load.
+#[derive(Debug, Error)]
+pub enum LoadSectionsError {
+    #[error("cannot seek to offset {0}")]
+    SeekFailed(u64, #[source] std::io::Error),
+
+    #[error("e_shentsize is not valid")]
+    InvalidSectionEntrySize,
+
+    #[error("e_shoff is not valid")]
+    InvalidSectionOffset,
+
+    #[error("the image is too small for {0} section headers")]
+    TooManySections(usize),
+
+    #[error("cannot read section headers")]
+    ReadSectionHeadersFailed(#[source] std::io::Error),
+
+    #[error("e_shstrndx is not valid")]
+    InvalidSectionNames,
+
+    #[error("cannot read section names")]
+    ReadSectionNamesFailed(#[source] std::io::Error),
+
+    #[error("section {0} has invalid name")]
+    InvalidSectionName(usize),
+}
+
+/// Represents an error for [`Elf::to_elf()`].
+#[derive(Debug, Error)]
+pub enum ToElfError {
+    #[error("the image is not a SELF")]
+    NotSelf,
+
+    #[error("cannot seek to offset {0:#018x}")]
+    SeekFailed(u64, #[source] std::io::Error),
+
+    #[error("cannot read ELF headers")]
+    ReadHeadersFailed(#[source] std::io::Error),
+
+    #[error("cannot map the programs to SELF segments")]
+    MapSegmentsFailed(#[source] MapSelfSegmentsError),
+
+    #[error("program {0} has invalid offset {1:#018x}")]
+    InvalidProgramOffset(usize, u64),
+
+    #[error("cannot read SELF segment #{0}")]
+    ReadSegmentFailed(usize, #[source] ReadSelfSegmentError),
+}
+
+/// Represents an error for [`Elf::read_self_segment()`].
+#[derive(Debug, Error)]
+pub enum ReadSelfSegmentError {
+    #[error("the image is not a SELF")]
+    NotSelf,
+
+    #[error("invalid segment index")]
+    InvalidIndex,
+
+    #[error("the segment is encrypted")]
+    EncryptedSegment,
+
+    #[error("offset {0:#018x} is not valid")]
+    InvalidOffset(u64),
+
+    #[error("{1} bytes at offset {0:#018x} is outside the image")]
+    OutOfImage(u64, u64),
+
+    #[error("the segment is too large ({0} bytes)")]
+    TooLarge(u64),
+
+    #[error("the segment is not compressed but its decompressed size is different")]
+    InvalidUncompressedSize,
+
     #[error("cannot seek to offset {0:#018x}")]
     SeekFailed(u64, #[source] std::io::Error),
 
     #[error("cannot read {1} bytes at offset {0:#018x}")]
     ReadFailed(u64, usize, #[source] std::io::Error),
+
+    #[error("cannot decompress the segment")]
+    DecompressFailed(#[source] flate2::DecompressError),
+
+    #[error("decompressed size ({0} bytes) does not match with the header")]
+    InvalidDecompressedSize(u64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    #[test]
+    fn test_bss_size() {
+        let image = plain(1, &[program(PT_LOAD, 0, 0x4000, 0x100, 0x4000)]);
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+        let prog = elf.program(0).unwrap();
+        let bss = (prog.addr() + prog.file_size() as usize)..prog.end();
+
+        assert_eq!(prog.file_size(), 0x100);
+        assert_eq!(prog.memory_size(), 0x4000);
+        assert_eq!(prog.bss_size(), 0x3f00);
+        assert_eq!(bss, 0x4100..0x8000);
+        assert_eq!(bss.len(), prog.bss_size());
+    }
+
+    #[test]
+    fn test_too_many_programs() {
+        let image = plain(1000, &[program(PT_LOAD, 0, 0, 0x100, 0x4000)]);
+
+        match Elf::open("test", Cursor::new(image)) {
+            Err(OpenError::TooManyPrograms(1000)) => {}
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("the image is opened successfully"),
+        }
+    }
+
+    #[test]
+    fn test_load_sections() {
+        let mut image = plain(1, &[program(PT_LOAD, 0, 0, 0x100, 0x4000)]);
+
+        sections(
+            &mut image,
+            b"\0.text\0.shstrtab\0",
+            &[(0, 0), (1, 1), (7, SHT_STRTAB)],
+        );
+
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+        let names: Vec<&str> = elf.sections().iter().map(|s| s.name()).collect();
+
+        assert!(elf.section_error().is_none());
+        assert_eq!(names, ["", ".text", ".shstrtab"]);
+    }
+
+    #[test]
+    fn test_invalid_section_entry_size() {
+        let mut image = plain(1, &[program(PT_LOAD, 0, 0, 0x100, 0x4000)]);
+
+        sections(&mut image, b"\0", &[(0, SHT_STRTAB)]);
+        LE::write_u16(&mut image[0x3a..], 0x20);
+
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(elf.sections().is_empty());
+        assert!(matches!(
+            elf.section_error(),
+            Some(LoadSectionsError::InvalidSectionEntrySize)
+        ));
+    }
+
+    #[test]
+    fn test_too_many_sections() {
+        let mut image = plain(1, &[program(PT_LOAD, 0, 0, 0x100, 0x4000)]);
+
+        sections(&mut image, b"\0", &[(0, SHT_STRTAB)]);
+        LE::write_u16(&mut image[0x3c..], 1000);
+
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(elf.sections().is_empty());
+        assert!(matches!(
+            elf.section_error(),
+            Some(LoadSectionsError::TooManySections(1000))
+        ));
+    }
+
+    #[test]
+    fn test_read_compressed_program() {
+        let data: Vec<u8> = (0..0x20).collect();
+        let elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let image = fself(&elf, &[(blocked(0, true), &deflate(&data), 0x20)]);
+        let mut elf = Elf::open("test", Cursor::new(image)).unwrap();
+        let mut buf = [0u8; 0x20];
+
+        elf.read_program(0, &mut buf).unwrap();
+
+        assert_eq!(buf.as_slice(), data.as_slice());
+        assert_eq!(elf.read_self_segment(0).unwrap(), data);
+    }
+
+    #[test]
+    fn test_segment_too_large() {
+        let elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let image = fself(
+            &elf,
+            &[(blocked(0, true), &deflate(&[0; 0x20]), 0x80000000)],
+        );
+        let mut elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(matches!(
+            elf.read_self_segment(0),
+            Err(ReadSelfSegmentError::TooLarge(0x80000000))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_uncompressed_size() {
+        let elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let image = fself(&elf, &[(blocked(0, false), &[0; 0x20], 0x40)]);
+        let mut elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(matches!(
+            elf.read_self_segment(0),
+            Err(ReadSelfSegmentError::InvalidUncompressedSize)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_decompressed_size() {
+        // The header claims a larger size than the data.
+        let data = deflate(&[0; 0x20]);
+        let elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let image = fself(&elf, &[(blocked(0, true), &data, 0x7fffffff)]);
+        let mut elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(matches!(
+            elf.read_self_segment(0),
+            Err(ReadSelfSegmentError::InvalidDecompressedSize(0x20))
+        ));
+
+        // The header claims a smaller size than the data.
+        let elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let image = fself(&elf, &[(blocked(0, true), &data, 0x10)]);
+        let mut elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(matches!(
+            elf.read_self_segment(0),
+            Err(ReadSelfSegmentError::InvalidDecompressedSize(0x10))
+        ));
+    }
+
+    #[test]
+    fn test_map_self_segments() {
+        let elf = plain(3, &mappable_programs());
+        let image = fself(
+            &elf,
+            &[
+                (blocked(1, false), &[0; 0x20], 0x20),
+                (blocked(0, false), &[0; 0x20], 0x20),
+                (blocked(2, false), &[0; 0x20], 0x20),
+            ],
+        );
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert_eq!(elf.map_self_segments().unwrap(), [(0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn test_map_self_segments_without_segment() {
+        // The second segment is for program 1 but it is not a blocked segment.
+        let elf = plain(3, &mappable_programs());
+        let image = fself(
+            &elf,
+            &[
+                (blocked(0, false), &[0; 0x20], 0x20),
+                (1 << 20, &[0; 0x20], 0x20),
+            ],
+        );
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert!(matches!(
+            elf.map_self_segments(),
+            Err(MapSelfSegmentsError::NoSegment(1))
+        ));
+    }
+
+    #[test]
+    fn test_to_elf() {
+        let data: Vec<u8> = (0..0x20).collect();
+        let mut elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+
+        // SELF usually keeps the section header fields from the original ELF.
+        LE::write_u64(&mut elf[0x28..], 0x10000);
+        LE::write_u16(&mut elf[0x3c..], 10);
+        LE::write_u16(&mut elf[0x3e..], 9);
+
+        let image = fself(&elf, &[(blocked(0, true), &deflate(&data), 0x20)]);
+        let mut elf = Elf::open("test", Cursor::new(image)).unwrap();
+        let out = elf.to_elf().unwrap();
+
+        assert_eq!(out.len(), 0x4020);
+        assert_eq!(&out[0x4000..], data.as_slice());
+        assert_eq!(LE::read_u64(&out[0x28..]), 0);
+        assert_eq!(LE::read_u16(&out[0x3c..]), 0);
+        assert_eq!(LE::read_u16(&out[0x3e..]), 0);
+
+        // The result must be a valid ELF.
+        let mut elf = Elf::open("test", Cursor::new(out)).unwrap();
+        let mut buf = [0u8; 0x20];
+
+        assert!(elf.self_segments().is_none());
+        assert!(elf.section_error().is_none());
+        assert_eq!(elf.programs().len(), 1);
+
+        elf.read_program(0, &mut buf).unwrap();
+
+        assert_eq!(buf.as_slice(), data.as_slice());
+    }
+
+    #[test]
+    fn test_self_header() {
+        let elf = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let mut image = fself(&elf, &[(blocked(0, false), &[0; 0x20], 0x20)]);
+
+        image[0x08] = 0x02;
+        image[0x09] = 0x04;
+
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert_eq!(elf.self_category(), Some(0x02));
+        assert_eq!(elf.self_program_type(), Some(0x04));
+
+        // Plain ELF does not have SELF header.
+        let image = plain(1, &[program(PT_LOAD, 0x4000, 0, 0x20, 0x4000)]);
+        let elf = Elf::open("test", Cursor::new(image)).unwrap();
+
+        assert_eq!(elf.self_category(), None);
+        assert_eq!(elf.self_program_type(), None);
+    }
+
+    const PT_LOAD: u32 = 1;
+    const PT_SCE_PROCPARAM: u32 = 0x61000001;
+    const PT_SCE_RELRO: u32 = 0x61000010;
+    const SHT_STRTAB: u32 = 3;
+
+    /// Builds a plain ELF with `phnum` in the header followed by `programs`.
+    fn plain(phnum: u16, programs: &[[u8; 0x38]]) -> Vec<u8> {
+        let mut image = vec![0u8; 0x40];
+
+        image[..4].copy_from_slice(&[0x7f, 0x45, 0x4c, 0x46]);
+        image[0x04] = 2;
+        image[0x05] = 1;
+        LE::write_u16(&mut image[0x10..], 2);
+        LE::write_u64(&mut image[0x20..], 0x40);
+        LE::write_u16(&mut image[0x36..], 0x38);
+        LE::write_u16(&mut image[0x38..], phnum);
+
+        for p in programs {
+            image.extend_from_slice(p);
+        }
+
+        image
+    }
+
+    /// Gets a PT_LOAD, PT_SCE_RELRO and PT_SCE_PROCPARAM that pass the checks in [`Elf::open()`].
+    fn mappable_programs() -> [[u8; 0x38]; 3] {
+        [
+            program(PT_LOAD, 0x4000, 0x8000, 0x20, 0x4000),
+            program(PT_SCE_RELRO, 0x8000, 0x4000, 0x20, 0x4000),
+            program(PT_SCE_PROCPARAM, 0xc000, 0x8000, 0x20, 0x20),
+        ]
+    }
+
+    /// Builds a program header with 16 KB alignment.
+    fn program(ty: u32, offset: u64, addr: u64, file_size: u64, memory_size: u64) -> [u8; 0x38] {
+        let mut data = [0u8; 0x38];
+
+        LE::write_u32(&mut data, ty);
+        LE::write_u64(&mut data[0x08..], offset);
+        LE::write_u64(&mut data[0x10..], addr);
+        LE::write_u64(&mut data[0x20..], file_size);
+        LE::write_u64(&mut data[0x28..], memory_size);
+        LE::write_u64(&mut data[0x30..], 0x4000);
+
+        data
+    }
+
+    /// Appends `names` and the section headers to `image`. Each header is a pair of its `sh_name`
+    /// and `sh_type`. The header with `SHT_STRTAB` will be used as the section names.
+    fn sections(image: &mut Vec<u8>, names: &[u8], headers: &[(u32, u32)]) {
+        let names_offset = image.len() as u64;
+
+        image.extend_from_slice(names);
+
+        let shoff = image.len() as u64;
+        let mut shstrndx = 0;
+
+        for (i, &(name, ty)) in headers.iter().enumerate() {
+            let mut data = [0u8; 0x40];
+
+            LE::write_u32(&mut data, name);
+            LE::write_u32(&mut data[0x04..], ty);
+
+            if ty == SHT_STRTAB {
+                LE::write_u64(&mut data[0x18..], names_offset);
+                LE::write_u64(&mut data[0x20..], names.len() as u64);
+                shstrndx = i as u16;
+            }
+
+            image.extend_from_slice(&data);
+        }
+
+        LE::write_u64(&mut image[0x28..], shoff);
+        LE::write_u16(&mut image[0x3a..], 0x40);
+        LE::write_u16(&mut image[0x3c..], headers.len() as u16);
+        LE::write_u16(&mut image[0x3e..], shstrndx);
+    }
+
+    /// Builds a SELF with `elf` as the ELF header and the program headers. Each segment is a tuple
+    /// of its flags, data and decompressed size.
+    fn fself(elf: &[u8], segments: &[(u64, &[u8], u64)]) -> Vec<u8> {
+        let mut image = vec![0u8; 0x20];
+
+        image[..8].copy_from_slice(&SELF_MAGIC);
+        image[0x08] = 1;
+        image[0x09] = 1;
+        LE::write_u16(&mut image[0x18..], segments.len() as u16);
+        LE::write_u16(&mut image[0x1a..], 0x22);
+
+        // Segment headers.
+        let mut offset = (0x20 + segments.len() * 0x20 + elf.len()) as u64;
+
+        for &(flags, data, decompressed_size) in segments {
+            let mut hdr = [0u8; 0x20];
+
+            LE::write_u64(&mut hdr, flags);
+            LE::write_u64(&mut hdr[0x08..], offset);
+            LE::write_u64(&mut hdr[0x10..], data.len() as u64);
+            LE::write_u64(&mut hdr[0x18..], decompressed_size);
+
+            image.extend_from_slice(&hdr);
+            offset += data.len() as u64;
+        }
+
+        // ELF header and segment data.
+        image.extend_from_slice(elf);
+
+        for (_, data, _) in segments {
+            image.extend_from_slice(data);
+        }
+
+        image
+    }
+
+    /// Gets the flags of SELF segment that contains the data of program `index`.
+    fn blocked(index: usize, compressed: bool) -> u64 {
+        let mut flags = SelfSegmentFlags::SF_BFLG.bits() | ((index as u64) << 20);
+
+        if compressed {
+            flags |= SelfSegmentFlags::SF_DFLG.bits();
+        }
+
+        flags
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut e = ZlibEncoder::new(Vec::new(), Compression::default());
+
+        e.write_all(data).unwrap();
+        e.finish().unwrap()
+    }
 }
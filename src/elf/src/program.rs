@@ -62,6 +62,11 @@ impl Program {
         self.memory_size
     }
 
+    /// Gets the size of zero-filled memory after the file data (e.g. `.bss`).
+    pub fn bss_size(&self) -> usize {
+        self.memory_size.saturating_sub(self.file_size as usize)
+    }
+
     pub fn alignment(&self) -> usize {
         self.alignment
     }
@@ -5,7 +5,11 @@ use std::fmt::{Display, Formatter};
 pub struct FileType(u16);
 
 impl FileType {
+    pub const ET_NONE: Self = Self(0x0000);
+    pub const ET_REL: Self = Self(0x0001);
     pub const ET_EXEC: Self = Self(0x0002);
+    pub const ET_DYN: Self = Self(0x0003);
+    pub const ET_CORE: Self = Self(0x0004);
     pub const ET_SCE_EXEC: Self = Self(0xfe00);
     pub const ET_SCE_REPLAY_EXEC: Self = Self(0xfe01);
     pub const ET_SCE_DYNEXEC: Self = Self(0xfe10);
@@ -19,7 +23,11 @@ impl FileType {
 impl Display for FileType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match *self {
+            Self::ET_NONE => f.write_str("ET_NONE"),
+            Self::ET_REL => f.write_str("ET_REL"),
             Self::ET_EXEC => f.write_str("ET_EXEC"),
+            Self::ET_DYN => f.write_str("ET_DYN"),
+            Self::ET_CORE => f.write_str("ET_CORE"),
             Self::ET_SCE_EXEC => f.write_str("ET_SCE_EXEC"),
             Self::ET_SCE_REPLAY_EXEC => f.write_str("ET_SCE_REPLAY_EXEC"),
             Self::ET_SCE_DYNEXEC => f.write_str("ET_SCE_DYNEXEC"),
@@ -1,6 +1,6 @@
 use self::cpu::{CpuState, ValueState};
 use crate::rtld::UnprotectedMemory;
-use iced_x86::{Code, Decoder, DecoderOptions, OpKind, Register};
+use iced_x86::{Code, ConditionCode, Decoder, DecoderOptions, OpKind, Register};
 use std::collections::{HashMap, VecDeque};
 use thiserror::Error;
 
@@ -72,6 +72,8 @@ impl<'a> Disassembler<'a> {
             refs: Vec::new(),
         };
 
+        let mut end = 0; // The farthest target of the forward branches.
+
         for i in decoder {
             // If the instruction is not valid that mean it is (likely) the end of function.
             if i.is_invalid() {
@@ -80,11 +82,42 @@ impl<'a> Disassembler<'a> {
 
             // Parse the instruction.
             let offset = (i.ip() - base) as usize;
+            let (i, last) = match i.code() {
+                Code::Add_r64_rm64 | Code::Add_rm64_r64 => {
+                    (self.disassemble_add(&i, &mut func, &mut cpu), false)
+                }
+                Code::Cmp_r64_rm64
+                | Code::Cmp_rm64_r64
+                | Code::Cmp_rm64_imm8
+                | Code::Cmp_rm64_imm32 => (self.disassemble_cmp(&i, &mut func, &mut cpu), false),
+                Code::Mov_r64_imm64 | Code::Mov_rm64_imm32 => {
+                    (self.disassemble_mov_imm(&i, &mut cpu), false)
+                }
+                Code::Mov_rm64_r64 => (self.disassemble_mov(&i, &mut func, &mut cpu), false),
+                Code::Retnq => (Instruction::Ret, true),
+                Code::Sub_rm64_imm8 => (self.disassemble_sub(&i), false),
+                Code::Test_rm64_r64 => (self.disassemble_test(&i, &mut func, &mut cpu), false),
+                Code::Xor_rm64_r64 => (self.disassemble_xor(&i, &mut cpu), false),
+                _ if i.is_jmp_short_or_near() => {
+                    let target = Self::branch_target(&i, base, offset)?;
+
+                    // Only follow the target if the code after this JMP is still needed by the
+                    // previous branches. Otherwise this JMP is the end of the function and the
+                    // target can be the other function (e.g. a tail call).
+                    if offset < end {
+                        end = end.max(target);
+                    }
+
+                    (Instruction::Jmp(target), true)
+                }
+                _ if i.is_jcc_short_or_near() => {
+                    let target = Self::branch_target(&i, base, offset)?;
+
+                    end = end.max(target);
 
-            match i.code() {
-                Code::Mov_rm64_r64 => self.disassemble_mov(&i, &mut func, &mut cpu),
-                Code::Sub_rm64_imm8 => self.disassemble_sub(&i),
-                Code::Xor_rm64_r64 => self.disassemble_xor(&i, &mut func, &mut cpu),
+                    (Instruction::Jcc(i.condition_code(), target), false)
+                }
+                _ if i.is_jmp_near_indirect() => (Instruction::JmpIndirect, true),
                 _ => {
                     let opcode = &module[offset..(offset + i.len())];
 
@@ -94,42 +127,118 @@ impl<'a> Disassembler<'a> {
                         i,
                     ));
                 }
+            };
+
+            func.instructions.push((offset, i));
+
+            // Stop if there are no more instructions that can be reached by the previous branches.
+            if last && end <= offset {
+                break;
             }
         }
 
         Ok(func)
     }
 
-    fn disassemble_mov(&self, i: &iced_x86::Instruction, f: &mut Function, c: &mut CpuState) {
+    /// Gets the target of the near branch `i` at `offset` as an offset in the mapped memory.
+    fn branch_target(
+        i: &iced_x86::Instruction,
+        base: u64,
+        offset: usize,
+    ) -> Result<usize, DisassembleError> {
+        let target = i.near_branch_target();
+
+        match target.checked_sub(base) {
+            Some(v) => Ok(v as usize),
+            None => Err(DisassembleError::InvalidBranchTarget(offset, target)),
+        }
+    }
+
+    fn disassemble_add(
+        &self,
+        i: &iced_x86::Instruction,
+        f: &mut Function,
+        c: &mut CpuState,
+    ) -> Instruction {
         if i.op0_kind() == OpKind::Memory {
-            panic!("MOV with the first operand is a memory is not supported yet.");
+            if i.has_lock_prefix() {
+                panic!("ADD with LOCK prefix is not supported yet.");
+            } else {
+                panic!("ADD with the first operand as memory is not supported yet.");
+            }
         } else if i.op1_kind() == OpKind::Memory {
-            panic!("MOV with the second operand is a memory is not supported yet.");
+            panic!("ADD with the second operand as memory is not supported yet.");
         } else {
             let dst = i.op0_register();
-            let src = i.op1_register();
+            let src = self.read_register(i.op1_register(), f, c);
 
-            // Check the second operand.
-            let src: Operand = match c.register(src) {
-                ValueState::FromCaller => {
-                    let i = f.params.len();
+            // The first operand is also a source.
+            self.read_register(dst, f, c);
 
-                    f.params.push(src.into());
-                    c.set_register(src, ValueState::Param(i));
+            // Set destination state.
+            c.set_register(dst, ValueState::Local);
 
-                    Operand::Param(i)
-                }
-                ValueState::Param(i) => Operand::Param(*i),
-                ValueState::Local => src.into(),
-            };
+            Instruction::Add(dst.into(), src)
+        }
+    }
+
+    fn disassemble_cmp(
+        &self,
+        i: &iced_x86::Instruction,
+        f: &mut Function,
+        c: &mut CpuState,
+    ) -> Instruction {
+        if i.op0_kind() == OpKind::Memory {
+            panic!("CMP with the first operand as memory is not supported yet.");
+        }
+
+        let lhs = self.read_register(i.op0_register(), f, c);
+
+        match i.op1_kind() {
+            OpKind::Register => Instruction::Cmp(lhs, self.read_register(i.op1_register(), f, c)),
+            OpKind::Immediate8to64 | OpKind::Immediate32to64 => {
+                // The immediate is already sign-extended to 64-bit.
+                Instruction::CmpImm(lhs, i.immediate(1))
+            }
+            v => panic!("CMP with the second operand is {v:?} is not supported yet."),
+        }
+    }
+
+    fn disassemble_mov(
+        &self,
+        i: &iced_x86::Instruction,
+        f: &mut Function,
+        c: &mut CpuState,
+    ) -> Instruction {
+        if i.op0_kind() == OpKind::Memory {
+            panic!("MOV with the first operand is a memory is not supported yet.");
+        } else if i.op1_kind() == OpKind::Memory {
+            panic!("MOV with the second operand is a memory is not supported yet.");
+        } else {
+            let dst = i.op0_register();
+            let src = self.read_register(i.op1_register(), f, c);
 
             // Set destination state.
             c.set_register(dst, ValueState::Local);
-            f.instructions.push(Instruction::Mov(dst.into(), src));
+
+            Instruction::Mov(dst.into(), src)
         }
     }
 
-    fn disassemble_sub(&self, i: &iced_x86::Instruction) {
+    fn disassemble_mov_imm(&self, i: &iced_x86::Instruction, c: &mut CpuState) -> Instruction {
+        if i.op0_kind() == OpKind::Memory {
+            panic!("MOV with the first operand is a memory is not supported yet.");
+        }
+
+        // The immediate is already sign-extended to 64-bit.
+        let dst = i.op0_register();
+
+        c.set_register(dst, ValueState::Local);
+
+        Instruction::MovImm(dst.into(), i.immediate(1))
+    }
+
+    fn disassemble_sub(&self, i: &iced_x86::Instruction) -> Instruction {
         if i.op0_kind() == OpKind::Memory {
             if i.has_lock_prefix() {
                 panic!("SUB with LOCK prefix is not supported yet.");
@@ -137,13 +246,14 @@ impl<'a> Disassembler<'a> {
                 panic!("SUB with the first operand is a memory is not supported yet.");
             }
         } else if i.op0_register() == Register::RSP {
-            // This SUB is a stack allocation. We don't need to add any instructions to the function
-            // here because we don't control a stack allocation on the codegen side.
+            // This SUB is a stack allocation. We still need to keep it because it is a part of the
+            // control flow (e.g. a branch target) and it also set the status flags.
             if i.op1_kind() != OpKind::Immediate8to64 {
                 panic!("SUB RSP with non-immediate value is not supported yet.");
             }
 
-            return;
+            // The immediate is already sign-extended to 64-bit.
+            Instruction::StackAlloc(i.immediate(1))
         } else {
             panic!(
                 "SUB with the first operand as the other regiser than RSP is not supported yet."
@@ -151,8 +261,24 @@ impl<'a> Disassembler<'a> {
         }
     }
 
-    fn disassemble_xor(&self, i: &iced_x86::Instruction, f: &mut Function, c: &mut CpuState) {
-        let i = if i.op0_kind() == OpKind::Memory {
+    fn disassemble_test(
+        &self,
+        i: &iced_x86::Instruction,
+        f: &mut Function,
+        c: &mut CpuState,
+    ) -> Instruction {
+        if i.op0_kind() == OpKind::Memory {
+            panic!("TEST with the first operand as memory is not supported yet.");
+        }
+
+        let lhs = self.read_register(i.op0_register(), f, c);
+        let rhs = self.read_register(i.op1_register(), f, c);
+
+        Instruction::Test(lhs, rhs)
+    }
+
+    fn disassemble_xor(&self, i: &iced_x86::Instruction, c: &mut CpuState) -> Instruction {
+        if i.op0_kind() == OpKind::Memory {
             if i.has_lock_prefix() {
                 panic!("XOR with LOCK prefix is not supported yet.");
             } else {
@@ -176,9 +302,23 @@ impl<'a> Disassembler<'a> {
                 }
                 v => panic!("XOR with the second operand is {v:?} is not supported yet."),
             }
-        };
+        }
+    }
+
+    /// Gets the operand to read `r` at the current point of the function.
+    fn read_register(&self, r: Register, f: &mut Function, c: &mut CpuState) -> Operand {
+        match c.register(r) {
+            ValueState::FromCaller => {
+                let i = f.params.len();
+
+                f.params.push(r.into());
+                c.set_register(r, ValueState::Param(i));
 
-        f.instructions.push(i);
+                Operand::Param(i)
+            }
+            ValueState::Param(i) => Operand::Param(*i),
+            ValueState::Local => r.into(),
+        }
     }
 }
 
@@ -186,13 +326,29 @@ impl<'a> Disassembler<'a> {
 pub(super) struct Function {
     params: Vec<Param>,
     returns: Vec<iced_x86::Register>,
-    instructions: Vec<Instruction>,
+    instructions: Vec<(usize, Instruction)>, // Offset in the mapped memory and the instruction.
     calls: Vec<usize>,
     refs: Vec<usize>,
 }
 
 impl Function {
-    pub fn instructions(&self) -> &[Instruction] {
+    #[cfg(test)]
+    pub fn new(instructions: Vec<(usize, Instruction)>) -> Self {
+        Self {
+            params: Vec::new(),
+            returns: Vec::new(),
+            instructions,
+            calls: Vec::new(),
+            refs: Vec::new(),
+        }
+    }
+
+    pub fn params(&self) -> &[Param] {
+        self.params.as_ref()
+    }
+
+    /// Gets a slice of the instructions ordered by its offset in the mapped memory.
+    pub fn instructions(&self) -> &[(usize, Instruction)] {
         self.instructions.as_ref()
     }
 
@@ -209,14 +365,14 @@ impl Function {
 
 /// Represents a function parameter.
 pub(super) enum Param {
-    Int(usize),
+    Int(usize, Register), // Size in bits and the register that passing the value.
 }
 
 impl From<iced_x86::Register> for Param {
     fn from(value: Register) -> Self {
         match value {
-            Register::RDI => Self::Int(64),
-            Register::RSI => Self::Int(64),
+            Register::RDI => Self::Int(64, value),
+            Register::RSI => Self::Int(64, value),
             v => panic!("Register {v:?} is not supported yet."),
         }
     }
@@ -224,7 +380,17 @@ impl From<iced_x86::Register> for Param {
 
 /// Represents a normalized CPU instruction.
 pub(super) enum Instruction {
+    Add(Operand, Operand),
+    Cmp(Operand, Operand),
+    CmpImm(Operand, u64),
+    Jcc(ConditionCode, usize),
+    Jmp(usize),
+    JmpIndirect,
     Mov(Operand, Operand),
+    MovImm(Operand, u64),
+    Ret,
+    StackAlloc(u64), // SUB RSP with the size to allocate.
+    Test(Operand, Operand),
     Zero(Operand),
 }
 
@@ -276,4 +442,7 @@ impl From<iced_x86::Register> for Operand {
 pub enum DisassembleError {
     #[error("unknown instruction '{2}' ({1:02x?}) at {0:#018x}")]
     UnknownInstruction(usize, Vec<u8>, iced_x86::Instruction),
+
+    #[error("the branch at {0:#018x} has target {1:#018x} before the module")]
+    InvalidBranchTarget(usize, u64),
 }
@@ -1,5 +1,10 @@
-use crate::disasm::Disassembler;
+use crate::disasm::{Disassembler, Function, Instruction, Operand, Param};
+use crate::llvm::builder::LlvmBuilder;
+use crate::llvm::function::{LlvmBlock, LlvmFunction, LlvmValue};
 use crate::llvm::module::LlvmModule;
+use iced_x86::{ConditionCode, Register};
+use std::collections::BTreeSet;
+use std::ops::Range;
 use thiserror::Error;
 
 /// Contains states for lifting a module.
@@ -13,11 +18,613 @@ impl<'a> Codegen<'a> {
         Self { input, output }
     }
 
-    pub fn lift(&mut self, _offset: usize) -> Result<(), LiftError> {
+    /// `offset` is an offset of the target **function** in the mapped memory. The lifted function
+    /// can be looked up with the name from [`function_name()`].
+    pub fn lift(&mut self, offset: usize) -> Result<LlvmFunction<'_>, LiftError> {
+        // Get the target function.
+        let func = match self.input.get(offset) {
+            Some(v) => v,
+            None => return Err(LiftError::NotDisassembled),
+        };
+
+        Self::lift_function(self.output, offset, func)
+    }
+
+    fn lift_function<'b>(
+        output: &'b LlvmModule,
+        offset: usize,
+        func: &Function,
+    ) -> Result<LlvmFunction<'b>, LiftError> {
+        // Split the function into basic blocks.
+        let blocks = discover_blocks(func)?;
+
+        // Create LLVM function.
+        let name = function_name(offset);
+
+        if output.get_function(&name).is_some() {
+            return Err(LiftError::AlreadyLifted(name));
+        }
+
+        let ir = output.create_function(&name);
+        let entry = ir.append_block("entry");
+        let labels: Vec<(usize, LlvmBlock)> = blocks
+            .iter()
+            .map(|b| (b.start, ir.append_block(&format!("{:#018x}", b.start))))
+            .collect();
+        let mut b = output.create_builder();
+
+        b.position_at_end(entry);
+
+        // Get pointers to the registers. The parameters are already in the register file.
+        let regs = Registers::new(&mut b, output, ir.param(0));
+
+        b.br(labels[0].1);
+
+        // Lift instructions.
+        for (n, block) in blocks.iter().enumerate() {
+            let instructions = &func.instructions()[block.instructions.clone()];
+
+            let next = labels.get(n + 1).map(|l| l.1);
+
+            b.position_at_end(labels[n].1);
+
+            for (offset, i) in instructions {
+                Self::lift_instruction(func, &regs, &labels, next, &mut b, *offset, i)?;
+            }
+
+            // Jump to the next block if the last instruction is not a branch.
+            match instructions.last() {
+                Some((_, Instruction::Jcc(..) | Instruction::Jmp(_) | Instruction::Ret)) => {}
+                _ => b.br(labels[n + 1].1),
+            }
+        }
+
+        Ok(ir)
+    }
+
+    /// `next` is the block after the current block and `offset` is the offset of `i`.
+    fn lift_instruction(
+        func: &Function,
+        regs: &Registers,
+        labels: &[(usize, LlvmBlock)],
+        next: Option<LlvmBlock>,
+        b: &mut LlvmBuilder,
+        offset: usize,
+        i: &Instruction,
+    ) -> Result<(), LiftError> {
+        // An operand that we cannot lift yet (e.g. a parameter in an unhandled register).
+        let operand = |op: &Operand| match regs.operand(func, op) {
+            Some(v) => Ok(v),
+            None => Err(LiftError::UnsupportedInstruction(offset)),
+        };
+
+        match i {
+            Instruction::Add(dst, src) => {
+                let dst = operand(dst)?;
+                let lhs = b.load_i64(dst);
+                let rhs = operand(src)?;
+                let rhs = b.load_i64(rhs);
+                let v = b.add(lhs, rhs);
+
+                b.store(v, dst);
+
+                Self::set_add_flags(b, regs, lhs, rhs, v);
+            }
+            Instruction::Cmp(lhs, rhs) => {
+                let lhs = b.load_i64(operand(lhs)?);
+                let rhs = b.load_i64(operand(rhs)?);
+                let v = b.sub(lhs, rhs);
+
+                Self::set_sub_flags(b, regs, lhs, rhs, v);
+            }
+            Instruction::CmpImm(lhs, rhs) => {
+                let lhs = b.load_i64(operand(lhs)?);
+                let rhs = b.const_i64(*rhs);
+                let v = b.sub(lhs, rhs);
+
+                Self::set_sub_flags(b, regs, lhs, rhs, v);
+            }
+            Instruction::Jcc(cc, target) => {
+                // The last instruction of the function is always a terminator so the next block
+                // always exists.
+                let cond = Self::condition(b, regs, offset, *cc)?;
+
+                b.cond_br(cond, Self::label(labels, *target), next.unwrap());
+            }
+            Instruction::Jmp(target) => b.br(Self::label(labels, *target)),
+            Instruction::JmpIndirect => return Err(LiftError::UnsupportedInstruction(offset)),
+            Instruction::Mov(dst, src) => {
+                let src = operand(src)?;
+                let v = b.load_i64(src);
+
+                b.store(v, operand(dst)?);
+            }
+            Instruction::MovImm(dst, v) => {
+                let v = b.const_i64(*v);
+
+                b.store(v, operand(dst)?);
+            }
+            Instruction::Ret => b.ret_void(),
+            Instruction::StackAlloc(size) => {
+                let lhs = b.load_i64(regs.rsp);
+                let rhs = b.const_i64(*size);
+                let v = b.sub(lhs, rhs);
+
+                b.store(v, regs.rsp);
+
+                Self::set_sub_flags(b, regs, lhs, rhs, v);
+            }
+            Instruction::Test(lhs, rhs) => {
+                let lhs = b.load_i64(operand(lhs)?);
+                let rhs = b.load_i64(operand(rhs)?);
+                let v = b.and(lhs, rhs);
+
+                Self::set_logic_flags(b, regs, v);
+            }
+            Instruction::Zero(dst) => {
+                let v = b.const_i64(0);
+
+                b.store(v, operand(dst)?);
+
+                Self::set_logic_flags(b, regs, v);
+            }
+        }
+
         Ok(())
     }
+
+    /// Gets the block that start at `target`.
+    fn label(labels: &[(usize, LlvmBlock)], target: usize) -> LlvmBlock {
+        // All branch targets are a leader so the block will always be found.
+        let i = labels.binary_search_by_key(&target, |l| l.0).unwrap();
+
+        labels[i].1
+    }
+
+    /// Gets `cc` of the instruction at `offset` as `i1`.
+    fn condition(
+        b: &mut LlvmBuilder,
+        regs: &Registers,
+        offset: usize,
+        cc: ConditionCode,
+    ) -> Result<LlvmValue, LiftError> {
+        let (cond, negate) = match decode_condition(cc) {
+            Some(v) => v,
+            None => return Err(LiftError::UnhandledCondition(offset, cc)),
+        };
+
+        let v = match cond {
+            Condition::Flag(bit) => Self::flag(b, regs, bit),
+            Condition::BelowOrEqual => Self::below_or_equal(b, regs),
+            Condition::Less => Self::less(b, regs),
+            Condition::LessOrEqual => Self::less_or_equal(b, regs),
+        };
+
+        if negate {
+            Ok(b.not(v))
+        } else {
+            Ok(v)
+        }
+    }
+
+    /// CF = 1 or ZF = 1.
+    fn below_or_equal(b: &mut LlvmBuilder, regs: &Registers) -> LlvmValue {
+        let cf = Self::flag(b, regs, CF);
+        let zf = Self::flag(b, regs, ZF);
+
+        b.or(cf, zf)
+    }
+
+    /// SF != OF.
+    fn less(b: &mut LlvmBuilder, regs: &Registers) -> LlvmValue {
+        let sf = Self::flag(b, regs, SF);
+        let of = Self::flag(b, regs, OF);
+
+        b.xor(sf, of)
+    }
+
+    /// ZF = 1 or SF != OF.
+    fn less_or_equal(b: &mut LlvmBuilder, regs: &Registers) -> LlvmValue {
+        let zf = Self::flag(b, regs, ZF);
+        let l = Self::less(b, regs);
+
+        b.or(zf, l)
+    }
+
+    /// Gets the status flag `bit` from RFLAGS as `i1`.
+    fn flag(b: &mut LlvmBuilder, regs: &Registers, bit: u64) -> LlvmValue {
+        let v = b.load_i64(regs.rflags);
+        let bit = b.const_i64(bit);
+        let v = b.lshr(v, bit);
+
+        b.trunc_i1(v)
+    }
+
+    /// Sets the status flags for `res = lhs + rhs`.
+    fn set_add_flags(
+        b: &mut LlvmBuilder,
+        regs: &Registers,
+        lhs: LlvmValue,
+        rhs: LlvmValue,
+        res: LlvmValue,
+    ) {
+        let zero = b.const_i64(0);
+        let zf = b.icmp_eq(res, zero);
+        let sf = b.icmp_slt(res, zero);
+        let cf = b.icmp_ult(res, lhs);
+
+        // Overflow when both operands have the same sign but the result has a different sign.
+        let x = b.xor(lhs, res);
+        let y = b.xor(rhs, res);
+        let v = b.and(x, y);
+        let of = b.icmp_slt(v, zero);
+
+        Self::set_flags(b, regs, &[(CF, cf), (ZF, zf), (SF, sf), (OF, of)]);
+    }
+
+    /// Sets the status flags for `res = lhs - rhs`.
+    fn set_sub_flags(
+        b: &mut LlvmBuilder,
+        regs: &Registers,
+        lhs: LlvmValue,
+        rhs: LlvmValue,
+        res: LlvmValue,
+    ) {
+        let zero = b.const_i64(0);
+        let zf = b.icmp_eq(res, zero);
+        let sf = b.icmp_slt(res, zero);
+        let cf = b.icmp_ult(lhs, rhs);
+
+        // Overflow when the operands have a different sign and the result has a different sign
+        // than the first operand.
+        let x = b.xor(lhs, rhs);
+        let y = b.xor(lhs, res);
+        let v = b.and(x, y);
+        let of = b.icmp_slt(v, zero);
+
+        Self::set_flags(b, regs, &[(CF, cf), (ZF, zf), (SF, sf), (OF, of)]);
+    }
+
+    /// Sets the status flags for the result of a logical instruction (e.g. AND, TEST and XOR).
+    fn set_logic_flags(b: &mut LlvmBuilder, regs: &Registers, res: LlvmValue) {
+        let zero = b.const_i64(0);
+        let zf = b.icmp_eq(res, zero);
+        let sf = b.icmp_slt(res, zero);
+
+        Self::set_flags(b, regs, &[(ZF, zf), (SF, sf)]);
+    }
+
+    /// Replaces CF, ZF, SF and OF in RFLAGS with `flags`. Each flag is a pair of its bit and `i1`
+    /// value. The flags that are not in `flags` will be cleared.
+    fn set_flags(b: &mut LlvmBuilder, regs: &Registers, flags: &[(u64, LlvmValue)]) {
+        let mask = b.const_i64(!((1 << CF) | (1 << ZF) | (1 << SF) | (1 << OF)));
+        let v = b.load_i64(regs.rflags);
+        let mut v = b.and(v, mask);
+
+        for &(bit, f) in flags {
+            let f = b.zext_i64(f);
+            let bit = b.const_i64(bit);
+            let f = b.shl(f, bit);
+
+            v = b.or(v, f);
+        }
+
+        b.store(v, regs.rflags);
+    }
+}
+
+/// Gets the name of the LLVM function for the function at `offset` in the mapped memory.
+pub fn function_name(offset: usize) -> String {
+    format!("{offset:#018x}")
+}
+
+/// Splits `func` into basic blocks.
+fn discover_blocks(func: &Function) -> Result<Vec<BasicBlock>, LiftError> {
+    let instructions = func.instructions();
+
+    // Check if the function has a terminator.
+    match instructions.last() {
+        Some((_, Instruction::Jmp(_) | Instruction::Ret)) => {}
+        _ => return Err(LiftError::NoTerminator),
+    }
+
+    // Find the leaders. The index of the instruction is used as a leader here.
+    let mut leaders = BTreeSet::from([0]);
+    let index =
+        |offset: usize, target: usize| match instructions.binary_search_by_key(&target, |i| i.0) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(LiftError::InvalidTarget(offset, target)),
+        };
+
+    for (i, (offset, ins)) in instructions.iter().enumerate() {
+        match ins {
+            Instruction::Jcc(_, target) | Instruction::Jmp(target) => {
+                leaders.insert(index(*offset, *target)?);
+            }
+            Instruction::JmpIndirect => return Err(LiftError::UnresolvedTarget(*offset)),
+            Instruction::Ret => {}
+            _ => continue,
+        }
+
+        // The next instruction is a fall-through successor or a target of the other branches.
+        if i + 1 < instructions.len() {
+            leaders.insert(i + 1);
+        }
+    }
+
+    // Build the blocks.
+    let mut blocks = Vec::with_capacity(leaders.len());
+    let mut leaders = leaders.into_iter().peekable();
+
+    while let Some(start) = leaders.next() {
+        let end = leaders.peek().copied().unwrap_or(instructions.len());
+
+        blocks.push(BasicBlock {
+            start: instructions[start].0,
+            instructions: start..end,
+        });
+    }
+
+    Ok(blocks)
+}
+
+/// Gets the condition to test for `cc` and whether its result needs to be negated.
+fn decode_condition(cc: ConditionCode) -> Option<(Condition, bool)> {
+    let v = match cc {
+        ConditionCode::o => (Condition::Flag(OF), false),
+        ConditionCode::no => (Condition::Flag(OF), true),
+        ConditionCode::b => (Condition::Flag(CF), false),
+        ConditionCode::ae => (Condition::Flag(CF), true),
+        ConditionCode::e => (Condition::Flag(ZF), false),
+        ConditionCode::ne => (Condition::Flag(ZF), true),
+        ConditionCode::be => (Condition::BelowOrEqual, false),
+        ConditionCode::a => (Condition::BelowOrEqual, true),
+        ConditionCode::s => (Condition::Flag(SF), false),
+        ConditionCode::ns => (Condition::Flag(SF), true),
+        ConditionCode::l => (Condition::Less, false),
+        ConditionCode::ge => (Condition::Less, true),
+        ConditionCode::le => (Condition::LessOrEqual, false),
+        ConditionCode::g => (Condition::LessOrEqual, true),
+        _ => return None,
+    };
+
+    Some(v)
+}
+
+// The bit of each status flag in RFLAGS. Other flags are not modeled yet.
+const CF: u64 = 0;
+const ZF: u64 = 6;
+const SF: u64 = 7;
+const OF: u64 = 11;
+
+/// Represents a test on the status flags.
+#[derive(Debug, PartialEq, Eq)]
+enum Condition {
+    Flag(u64),    // The flag is set.
+    BelowOrEqual, // CF = 1 or ZF = 1.
+    Less,         // SF != OF.
+    LessOrEqual,  // ZF = 1 or SF != OF.
+}
+
+/// Represents a basic block of the function.
+struct BasicBlock {
+    start: usize,               // Offset in the mapped memory.
+    instructions: Range<usize>, // Range in [`Function::instructions()`].
+}
+
+/// Contains a pointer to each general-purpose register in the register file.
+struct Registers {
+    rax: LlvmValue,
+    rbp: LlvmValue,
+    rbx: LlvmValue,
+    rcx: LlvmValue,
+    rdi: LlvmValue,
+    rdx: LlvmValue,
+    rsi: LlvmValue,
+    rsp: LlvmValue,
+    r8: LlvmValue,
+    r9: LlvmValue,
+    r10: LlvmValue,
+    r11: LlvmValue,
+    r12: LlvmValue,
+    r13: LlvmValue,
+    r14: LlvmValue,
+    r15: LlvmValue,
+    rflags: LlvmValue,
+}
+
+impl Registers {
+    /// `file` is a pointer to [`LlvmModule::register_file()`] of `m`.
+    fn new(b: &mut LlvmBuilder, m: &LlvmModule, file: LlvmValue) -> Self {
+        Self {
+            rax: m.gpr_ptr(b, file, Register::RAX),
+            rbp: m.gpr_ptr(b, file, Register::RBP),
+            rbx: m.gpr_ptr(b, file, Register::RBX),
+            rcx: m.gpr_ptr(b, file, Register::RCX),
+            rdi: m.gpr_ptr(b, file, Register::RDI),
+            rdx: m.gpr_ptr(b, file, Register::RDX),
+            rsi: m.gpr_ptr(b, file, Register::RSI),
+            rsp: m.gpr_ptr(b, file, Register::RSP),
+            r8: m.gpr_ptr(b, file, Register::R8),
+            r9: m.gpr_ptr(b, file, Register::R9),
+            r10: m.gpr_ptr(b, file, Register::R10),
+            r11: m.gpr_ptr(b, file, Register::R11),
+            r12: m.gpr_ptr(b, file, Register::R12),
+            r13: m.gpr_ptr(b, file, Register::R13),
+            r14: m.gpr_ptr(b, file, Register::R14),
+            r15: m.gpr_ptr(b, file, Register::R15),
+            rflags: b.struct_gep(m.register_file(), file, 17),
+        }
+    }
+
+    fn get(&self, r: Register) -> Option<LlvmValue> {
+        let v = match r {
+            Register::RAX => self.rax,
+            Register::RBP => self.rbp,
+            Register::RBX => self.rbx,
+            Register::RCX => self.rcx,
+            Register::RDI => self.rdi,
+            Register::RDX => self.rdx,
+            Register::RSI => self.rsi,
+            Register::R8 => self.r8,
+            Register::R9 => self.r9,
+            Register::R10 => self.r10,
+            Register::R11 => self.r11,
+            Register::R12 => self.r12,
+            Register::R13 => self.r13,
+            Register::R14 => self.r14,
+            Register::R15 => self.r15,
+            _ => return None,
+        };
+
+        Some(v)
+    }
+
+    /// Gets the storage of `op`. A parameter is still live in the register that passed it because
+    /// the disassembler will not produce [`Operand::Param`] once that register has been written.
+    fn operand(&self, f: &Function, op: &Operand) -> Option<LlvmValue> {
+        let v = match op {
+            Operand::Param(i) => match f.params().get(*i)? {
+                Param::Int(_, r) => self.get(*r)?,
+            },
+            Operand::Rax(_) => self.rax,
+            Operand::Rbp(_) => self.rbp,
+            Operand::Rbx(_) => self.rbx,
+            Operand::Rcx(_) => self.rcx,
+            Operand::Rdi(_) => self.rdi,
+            Operand::Rdx(_) => self.rdx,
+            Operand::Rsi(_) => self.rsi,
+            Operand::R8(_) => self.r8,
+            Operand::R9(_) => self.r9,
+            Operand::R10(_) => self.r10,
+            Operand::R11(_) => self.r11,
+            Operand::R12(_) => self.r12,
+            Operand::R13(_) => self.r13,
+            Operand::R14(_) => self.r14,
+            Operand::R15(_) => self.r15,
+        };
+
+        Some(v)
+    }
 }
 
 /// Represents an error for [`Codegen::lift()`].
 #[derive(Debug, Error)]
-pub enum LiftError {}
+pub enum LiftError {
+    #[error("the function is not disassembled")]
+    NotDisassembled,
+
+    #[error("function {0} is already lifted")]
+    AlreadyLifted(String),
+
+    #[error("the function does not end with a terminator")]
+    NoTerminator,
+
+    #[error("condition {1:?} of the branch at {0:#018x} is not supported yet")]
+    UnhandledCondition(usize, ConditionCode),
+
+    #[error("cannot resolve the target of the branch at {0:#018x}")]
+    UnresolvedTarget(usize),
+
+    #[error("the branch at {0:#018x} has invalid target {1:#018x}")]
+    InvalidTarget(usize, usize),
+
+    #[error("the instruction at {0:#018x} is not supported yet")]
+    UnsupportedInstruction(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llvm::Llvm;
+
+    #[test]
+    fn test_discover_blocks() {
+        // A loop that test RAX with CMP then jump back with JL.
+        let func = Function::new(vec![
+            (0x00, Instruction::MovImm(Operand::Rax(64), 0)),
+            (0x07, Instruction::Add(Operand::Rax(64), Operand::Rcx(64))),
+            (0x0a, Instruction::CmpImm(Operand::Rax(64), 10)),
+            (0x0e, Instruction::Jcc(ConditionCode::l, 0x07)),
+            (0x10, Instruction::Ret),
+        ]);
+
+        let blocks = discover_blocks(&func).unwrap();
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].start, 0x00);
+        assert_eq!(blocks[0].instructions, 0..1);
+        assert_eq!(blocks[1].start, 0x07);
+        assert_eq!(blocks[1].instructions, 1..4);
+        assert_eq!(blocks[2].start, 0x10);
+        assert_eq!(blocks[2].instructions, 4..5);
+    }
+
+    #[test]
+    fn test_lift_loop() {
+        // Same loop as test_discover_blocks.
+        let func = Function::new(vec![
+            (0x00, Instruction::MovImm(Operand::Rax(64), 0)),
+            (0x07, Instruction::Add(Operand::Rax(64), Operand::Rcx(64))),
+            (0x0a, Instruction::CmpImm(Operand::Rax(64), 10)),
+            (0x0e, Instruction::Jcc(ConditionCode::l, 0x07)),
+            (0x10, Instruction::Ret),
+        ]);
+
+        let llvm = Llvm::new();
+        let module = llvm.create_module("test");
+        let ir = Codegen::lift_function(&module, 0x1000, &func).unwrap();
+
+        // The entry block plus one block for each basic block of the function.
+        assert_eq!(ir.block_count(), 4);
+
+        // LLVM separates each block with an empty line.
+        let text = ir.to_string();
+        let block = |start: &str| {
+            text.split("\n\n")
+                .find(|b| b.trim_start_matches('"').starts_with(start))
+                .unwrap()
+        };
+
+        // The loop body must compute the flags from CMP then branch on SF != OF.
+        let body = block("0x0000000000000007");
+        let br = body.lines().last().unwrap().trim();
+
+        assert!(body.contains("sub i64"));
+        assert!(body.contains("icmp slt"));
+        assert!(body.contains("xor i1"));
+        assert!(br.starts_with("br i1"));
+
+        // The back-edge is taken when the condition is true, otherwise fall through to RET.
+        let back = br.find("0x0000000000000007").unwrap();
+        let exit = br.find("0x0000000000000010").unwrap();
+
+        assert!(back < exit);
+        assert!(block("0x0000000000000010").contains("ret void"));
+    }
+
+    #[test]
+    fn test_decode_condition() {
+        assert_eq!(
+            decode_condition(ConditionCode::e),
+            Some((Condition::Flag(ZF), false))
+        );
+        assert_eq!(
+            decode_condition(ConditionCode::ae),
+            Some((Condition::Flag(CF), true))
+        );
+        assert_eq!(
+            decode_condition(ConditionCode::a),
+            Some((Condition::BelowOrEqual, true))
+        );
+        assert_eq!(
+            decode_condition(ConditionCode::l),
+            Some((Condition::Less, false))
+        );
+        assert_eq!(
+            decode_condition(ConditionCode::g),
+            Some((Condition::LessOrEqual, true))
+        );
+        assert_eq!(decode_condition(ConditionCode::p), None);
+    }
+}
@@ -0,0 +1,212 @@
+use super::function::{LlvmBlock, LlvmType, LlvmValue};
+use super::Llvm;
+use llvm_sys::core::{
+    LLVMBuildAdd, LLVMBuildAnd, LLVMBuildBr, LLVMBuildCondBr, LLVMBuildICmp, LLVMBuildLShr,
+    LLVMBuildLoad2, LLVMBuildNot, LLVMBuildOr, LLVMBuildRetVoid, LLVMBuildShl, LLVMBuildStore,
+    LLVMBuildStructGEP2, LLVMBuildSub, LLVMBuildTrunc, LLVMBuildXor, LLVMBuildZExt, LLVMConstInt,
+    LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMInt1TypeInContext, LLVMInt64TypeInContext,
+    LLVMPositionBuilderAtEnd,
+};
+use llvm_sys::prelude::LLVMBuilderRef;
+use llvm_sys::LLVMIntPredicate;
+use std::ffi::c_char;
+
+/// A wrapper on LLVM IR builder.
+pub struct LlvmBuilder<'a> {
+    llvm: &'a Llvm,
+    builder: LLVMBuilderRef,
+}
+
+impl<'a> LlvmBuilder<'a> {
+    pub(super) fn new(llvm: &'a Llvm) -> Self {
+        let builder = llvm.with_context(|cx| unsafe { LLVMCreateBuilderInContext(cx) });
+
+        Self { llvm, builder }
+    }
+
+    pub fn position_at_end(&mut self, block: LlvmBlock) {
+        let b = self.builder;
+
+        self.llvm
+            .with_context(|_| unsafe { LLVMPositionBuilderAtEnd(b, block.0) });
+    }
+
+    pub fn const_i64(&mut self, v: u64) -> LlvmValue {
+        let v = self
+            .llvm
+            .with_context(|cx| unsafe { LLVMConstInt(LLVMInt64TypeInContext(cx), v, 0) });
+
+        LlvmValue(v)
+    }
+
+    /// Gets a pointer to the field `index` of the structure `ty` pointed by `ptr`.
+    pub fn struct_gep(&mut self, ty: LlvmType, ptr: LlvmValue, index: u32) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildStructGEP2(b, ty.0, ptr.0, index, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn load_i64(&mut self, ptr: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self.llvm.with_context(|cx| unsafe {
+            LLVMBuildLoad2(b, LLVMInt64TypeInContext(cx), ptr.0, NONAME)
+        });
+
+        LlvmValue(v)
+    }
+
+    pub fn store(&mut self, v: LlvmValue, ptr: LlvmValue) {
+        let b = self.builder;
+
+        self.llvm
+            .with_context(|_| unsafe { LLVMBuildStore(b, v.0, ptr.0) });
+    }
+
+    pub fn add(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildAdd(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn sub(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildSub(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn and(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildAnd(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn or(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildOr(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn xor(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildXor(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn shl(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildShl(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn lshr(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildLShr(b, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    pub fn not(&mut self, v: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildNot(b, v.0, NONAME) });
+
+        LlvmValue(v)
+    }
+
+    /// Zero-extends `v` to `i64`.
+    pub fn zext_i64(&mut self, v: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self.llvm.with_context(|cx| unsafe {
+            LLVMBuildZExt(b, v.0, LLVMInt64TypeInContext(cx), NONAME)
+        });
+
+        LlvmValue(v)
+    }
+
+    /// Truncates `v` to `i1`.
+    pub fn trunc_i1(&mut self, v: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self.llvm.with_context(|cx| unsafe {
+            LLVMBuildTrunc(b, v.0, LLVMInt1TypeInContext(cx), NONAME)
+        });
+
+        LlvmValue(v)
+    }
+
+    pub fn icmp_eq(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        self.icmp(LLVMIntPredicate::LLVMIntEQ, lhs, rhs)
+    }
+
+    pub fn icmp_slt(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        self.icmp(LLVMIntPredicate::LLVMIntSLT, lhs, rhs)
+    }
+
+    pub fn icmp_ult(&mut self, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        self.icmp(LLVMIntPredicate::LLVMIntULT, lhs, rhs)
+    }
+
+    pub fn br(&mut self, dest: LlvmBlock) {
+        let b = self.builder;
+
+        self.llvm
+            .with_context(|_| unsafe { LLVMBuildBr(b, dest.0) });
+    }
+
+    /// `cond` must be an `i1`.
+    pub fn cond_br(&mut self, cond: LlvmValue, then: LlvmBlock, otherwise: LlvmBlock) {
+        let b = self.builder;
+
+        self.llvm
+            .with_context(|_| unsafe { LLVMBuildCondBr(b, cond.0, then.0, otherwise.0) });
+    }
+
+    pub fn ret_void(&mut self) {
+        let b = self.builder;
+
+        self.llvm.with_context(|_| unsafe { LLVMBuildRetVoid(b) });
+    }
+
+    fn icmp(&mut self, pred: LLVMIntPredicate, lhs: LlvmValue, rhs: LlvmValue) -> LlvmValue {
+        let b = self.builder;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMBuildICmp(b, pred, lhs.0, rhs.0, NONAME) });
+
+        LlvmValue(v)
+    }
+}
+
+impl<'a> Drop for LlvmBuilder<'a> {
+    fn drop(&mut self) {
+        let b = self.builder;
+
+        self.llvm.with_context(|_| unsafe { LLVMDisposeBuilder(b) });
+    }
+}
+
+/// An empty name for the values that does not need to have a name.
+const NONAME: *const c_char = c"".as_ptr();
@@ -0,0 +1,77 @@
+use super::Llvm;
+use llvm_sys::core::{
+    LLVMAppendBasicBlockInContext, LLVMCountBasicBlocks, LLVMDisposeMessage, LLVMGetParam,
+    LLVMPrintValueToString,
+};
+use llvm_sys::prelude::{LLVMBasicBlockRef, LLVMTypeRef, LLVMValueRef};
+use std::ffi::{CStr, CString};
+use std::fmt::{Display, Formatter};
+
+/// A wrapper on LLVM function.
+///
+/// The function is owned by the module that created it so this object borrows that module.
+pub struct LlvmFunction<'a> {
+    llvm: &'a Llvm,
+    value: LLVMValueRef,
+}
+
+impl<'a> LlvmFunction<'a> {
+    pub(super) fn new(llvm: &'a Llvm, value: LLVMValueRef) -> Self {
+        Self { llvm, value }
+    }
+
+    pub fn param(&self, i: usize) -> LlvmValue {
+        let f = self.value;
+        let v = self
+            .llvm
+            .with_context(|_| unsafe { LLVMGetParam(f, i.try_into().unwrap()) });
+
+        LlvmValue(v)
+    }
+
+    pub fn append_block(&self, name: &str) -> LlvmBlock {
+        let f = self.value;
+        let name = CString::new(name).unwrap();
+        let b = self
+            .llvm
+            .with_context(|cx| unsafe { LLVMAppendBasicBlockInContext(cx, f, name.as_ptr()) });
+
+        LlvmBlock(b)
+    }
+
+    pub fn block_count(&self) -> usize {
+        let f = self.value;
+        let n = self
+            .llvm
+            .with_context(|_| unsafe { LLVMCountBasicBlocks(f) });
+
+        n.try_into().unwrap()
+    }
+}
+
+impl Display for LlvmFunction<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let v = self.value;
+        let ir = self.llvm.with_context(|_| unsafe {
+            let ir = LLVMPrintValueToString(v);
+            let owned = CStr::from_ptr(ir).to_string_lossy().into_owned();
+
+            LLVMDisposeMessage(ir);
+            owned
+        });
+
+        f.write_str(&ir)
+    }
+}
+
+/// A wrapper on LLVM basic block.
+#[derive(Clone, Copy)]
+pub struct LlvmBlock(pub(super) LLVMBasicBlockRef);
+
+/// A wrapper on LLVM type.
+#[derive(Clone, Copy)]
+pub struct LlvmType(pub(super) LLVMTypeRef);
+
+/// A wrapper on LLVM value.
+#[derive(Clone, Copy)]
+pub struct LlvmValue(pub(super) LLVMValueRef);
@@ -5,6 +5,8 @@ use std::ffi::{c_char, CStr, CString};
 use std::fmt::Display;
 use std::sync::{Arc, Mutex};
 
+pub mod builder;
+pub mod function;
 pub mod module;
 
 /// A LLVM wrapper for thread-safe.
@@ -1,10 +1,17 @@
+use super::builder::LlvmBuilder;
+use super::function::{LlvmFunction, LlvmType, LlvmValue};
 use super::{Error, Llvm};
-use llvm_sys::core::LLVMDisposeModule;
+use iced_x86::Register;
+use llvm_sys::core::{
+    LLVMAddFunction, LLVMArrayType2, LLVMDisposeModule, LLVMFunctionType, LLVMGetNamedFunction,
+    LLVMGetTypeByName2, LLVMInt128TypeInContext, LLVMInt64TypeInContext, LLVMPointerTypeInContext,
+    LLVMStructCreateNamed, LLVMStructSetBody, LLVMVoidTypeInContext,
+};
 use llvm_sys::execution_engine::{
     LLVMCreateExecutionEngineForModule, LLVMDisposeExecutionEngine, LLVMExecutionEngineRef,
 };
 use llvm_sys::prelude::LLVMModuleRef;
-use std::ffi::c_char;
+use std::ffi::{c_char, CString};
 use std::ptr::null_mut;
 use std::sync::Arc;
 
@@ -22,6 +29,98 @@ impl LlvmModule {
         }
     }
 
+    /// Adds a new function that accept a pointer to [`Self::register_file()`] and return nothing.
+    pub fn create_function(&self, name: &str) -> LlvmFunction<'_> {
+        let module = self.module;
+        let name = CString::new(name).unwrap();
+        let f = self.llvm.with_context(|cx| unsafe {
+            let mut params = [LLVMPointerTypeInContext(cx, 0)];
+            let ret = LLVMVoidTypeInContext(cx);
+            let ty = LLVMFunctionType(ret, params.as_mut_ptr(), params.len() as _, 0);
+
+            LLVMAddFunction(module, name.as_ptr(), ty)
+        });
+
+        LlvmFunction::new(&self.llvm, f)
+    }
+
+    /// Gets the type of the guest register file. The fields are in the following order:
+    ///
+    /// - 16 `i64` for GPRs in the order of their encoding (RAX, RCX, RDX, RBX, RSP, RBP, RSI, RDI
+    ///   then R8 to R15).
+    /// - `i64` for RIP.
+    /// - `i64` for RFLAGS.
+    /// - `[16 x i128]` for XMM0 to XMM15.
+    pub fn register_file(&self) -> LlvmType {
+        let ty = self.llvm.with_context(|cx| unsafe {
+            let name = c"RegisterFile".as_ptr();
+            let ty = LLVMGetTypeByName2(cx, name);
+
+            if !ty.is_null() {
+                return ty;
+            }
+
+            // Create the type.
+            let ty = LLVMStructCreateNamed(cx, name);
+            let mut fields = vec![LLVMInt64TypeInContext(cx); 18];
+
+            fields.push(LLVMArrayType2(LLVMInt128TypeInContext(cx), 16));
+
+            LLVMStructSetBody(ty, fields.as_mut_ptr(), fields.len() as _, 0);
+
+            ty
+        });
+
+        LlvmType(ty)
+    }
+
+    /// Gets a pointer to `reg` in the register file pointed by `file`. See [`Self::register_file()`]
+    /// for the layout.
+    ///
+    /// # Panics
+    /// If `reg` is not a 64-bit GPR.
+    pub fn gpr_ptr(&self, b: &mut LlvmBuilder, file: LlvmValue, reg: Register) -> LlvmValue {
+        let i = match reg {
+            Register::RAX => 0,
+            Register::RCX => 1,
+            Register::RDX => 2,
+            Register::RBX => 3,
+            Register::RSP => 4,
+            Register::RBP => 5,
+            Register::RSI => 6,
+            Register::RDI => 7,
+            Register::R8 => 8,
+            Register::R9 => 9,
+            Register::R10 => 10,
+            Register::R11 => 11,
+            Register::R12 => 12,
+            Register::R13 => 13,
+            Register::R14 => 14,
+            Register::R15 => 15,
+            v => panic!("Register {v:?} is not a 64-bit GPR."),
+        };
+
+        b.struct_gep(self.register_file(), file, i)
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<LlvmFunction<'_>> {
+        let module = self.module;
+        let name = CString::new(name).unwrap();
+        let f = self
+            .llvm
+            .with_context(|_| unsafe { LLVMGetNamedFunction(module, name.as_ptr()) });
+
+        if f.is_null() {
+            None
+        } else {
+            Some(LlvmFunction::new(&self.llvm, f))
+        }
+    }
+
+    pub fn create_builder(&self) -> LlvmBuilder<'_> {
+        LlvmBuilder::new(&self.llvm)
+    }
+
     pub fn create_execution_engine(mut self) -> Result<ExecutionEngine, Error> {
         let mut ee: LLVMExecutionEngineRef = null_mut();
         let module = self.module;
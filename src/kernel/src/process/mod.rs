@@ -59,7 +59,7 @@ impl VProc {
         let vp = Arc::new(Self {
             id: Self::new_id(),
             threads: mg.new_member(Vec::new()),
-            cred: Ucred::new(AuthInfo::EXE.clone()),
+            cred: Ucred::new(APP_UID, AuthInfo::EXE.clone()),
             group: mg.new_member(None),
             sigacts: mg.new_member(SignalActs::new()),
             files: VProcFiles::new(&mg),
@@ -133,7 +133,7 @@ impl VProc {
         let mut threads = self.threads.write();
 
         // TODO: Check how ucred is constructed for a thread.
-        let cred = Ucred::new(AuthInfo::EXE.clone());
+        let cred = Ucred::new(APP_UID, AuthInfo::EXE.clone());
         let td = Arc::new(VThread::new(Self::new_id(), cred, &self.mtxg));
         let active = Box::new(ActiveThread {
             proc: self.clone(),
@@ -549,4 +549,9 @@ pub enum VProcError {
     GetDataLimitFailed(#[source] std::io::Error),
 }
 
+/// The user ID of the application. We don't run the application as the superuser (0) because
+/// [`Ucred::priv_check()`] will grant every privilege to it, which would hide the privileges that
+/// the application actually needs.
+const APP_UID: i32 = 1;
+
 static NEXT_ID: AtomicI32 = AtomicI32::new(1);
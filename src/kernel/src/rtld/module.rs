@@ -72,7 +72,8 @@ impl<E: ExecutionEngine> Module<E> {
                 Err(e) => return Err(MapError::UnprotectSegmentFailed(i, e)),
             };
 
-            // Read ELF program.
+            // Read ELF program. No need to zero the remaining bss_size() bytes because the segment is
+            // an anonymous mapping.
             if let Err(e) = image.read_program(p, s.as_mut()) {
                 return Err(MapError::ReadProgramFailed(p, e));
             }
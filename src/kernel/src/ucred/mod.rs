@@ -11,18 +11,39 @@ mod privilege;
 /// An implementation of `ucred` structure.
 #[derive(Debug)]
 pub struct Ucred {
+    effective_uid: i32, // cr_uid
+    jailed: bool,       // TODO: Replace with cr_prison once the jail is implemented.
     auth: AuthInfo,
+    privileges: PrivilegeSet,
 }
 
 impl Ucred {
-    pub fn new(auth: AuthInfo) -> Self {
-        Self { auth }
+    pub fn new(effective_uid: i32, auth: AuthInfo) -> Self {
+        Self {
+            effective_uid,
+            jailed: false,
+            auth,
+            privileges: PrivilegeSet::new(),
+        }
+    }
+
+    pub fn effective_uid(&self) -> i32 {
+        self.effective_uid
     }
 
     pub fn auth(&self) -> &AuthInfo {
         &self.auth
     }
 
+    /// Gets the privileges that are explicitly granted to this credential.
+    pub fn privileges(&self) -> &PrivilegeSet {
+        &self.privileges
+    }
+
+    pub fn privileges_mut(&mut self) -> &mut PrivilegeSet {
+        &mut self.privileges
+    }
+
     /// See `sceSblACMgrIsWebcoreProcess` on the PS4 for a reference.
     pub fn is_webcore_process(&self) -> bool {
         // TODO: Implement this.
@@ -48,30 +69,99 @@ impl Ucred {
     }
 
     /// See `priv_check_cred` on the PS4 for a reference.
+    ///
+    /// # Panics
+    /// If `p` is not a known privilege and it was not granted to this credential.
     pub fn priv_check(&self, p: Privilege) -> Result<(), PrivilegeError> {
         // TODO: Check suser_enabled.
-        self.prison_priv_check()?;
+        self.prison_priv_check(p)?;
+
+        if self.privileges.contains(p) {
+            return Ok(());
+        }
 
-        let r = match p {
+        // Some privileges are always granted to the system credential.
+        let sce = matches!(
+            p,
             Privilege::MAXFILES
-            | Privilege::PROC_SETLOGIN
-            | Privilege::SCE680
-            | Privilege::SCE683
-            | Privilege::SCE686 => self.is_system(),
-            v => todo!("priv_check_cred(cred, {v})"),
-        };
-
-        if r {
-            Ok(())
-        } else {
-            Err(PrivilegeError::NoPrivilege)
+                | Privilege::PROC_SETLOGIN
+                | Privilege::SCE680
+                | Privilege::SCE683
+                | Privilege::SCE686
+        );
+
+        if sce && self.is_system() {
+            return Ok(());
         }
+
+        // We don't know the policy of the other privileges (e.g. the other SCE privileges) so we
+        // want to know when they are requested.
+        if Privilege::from_known(p.into()).is_none() {
+            todo!("priv_check_cred(cred, {p})");
+        }
+
+        // The superuser has all privileges.
+        if self.effective_uid == 0 {
+            return Ok(());
+        }
+
+        Err(PrivilegeError::NoPrivilege)
     }
 
     /// See `prison_priv_check` on the PS4 for a reference.
-    fn prison_priv_check(&self) -> Result<(), PrivilegeError> {
-        // TODO: Implement this.
-        Ok(())
+    fn prison_priv_check(&self, p: Privilege) -> Result<(), PrivilegeError> {
+        if !self.jailed {
+            return Ok(());
+        }
+
+        // The privileges that are allowed in the jail.
+        match p {
+            Privilege::KTRACE
+            | Privilege::PROC_SETLOGINCLASS
+            | Privilege::CRED_SETUID
+            | Privilege::CRED_SETEUID
+            | Privilege::CRED_SETGID
+            | Privilege::CRED_SETEGID
+            | Privilege::CRED_SETGROUPS
+            | Privilege::CRED_SETREUID
+            | Privilege::CRED_SETREGID
+            | Privilege::CRED_SETRESUID
+            | Privilege::CRED_SETRESGID
+            | Privilege::SEEOTHERGIDS
+            | Privilege::SEEOTHERUIDS
+            | Privilege::DEBUG_DIFFCRED
+            | Privilege::DEBUG_SUGID
+            | Privilege::DEBUG_UNPRIV
+            | Privilege::SIGNAL_DIFFCRED
+            | Privilege::SIGNAL_SUGID
+            | Privilege::SCHED_DIFFCRED
+            | Privilege::SCHED_CPUSET
+            | Privilege::PROC_LIMIT
+            | Privilege::PROC_SETRLIMIT
+            | Privilege::JAIL_ATTACH
+            | Privilege::IPC_READ
+            | Privilege::IPC_WRITE
+            | Privilege::IPC_ADMIN
+            | Privilege::IPC_MSGSIZE
+            | Privilege::MQ_ADMIN
+            | Privilege::VFS_READ
+            | Privilege::VFS_WRITE
+            | Privilege::VFS_ADMIN
+            | Privilege::VFS_EXEC
+            | Privilege::VFS_LOOKUP
+            | Privilege::VFS_BLOCKRESERVE
+            | Privilege::VFS_CHFLAGS_DEV
+            | Privilege::VFS_CHOWN
+            | Privilege::VFS_CHROOT
+            | Privilege::VFS_RETAINSUGID
+            | Privilege::VFS_FCHROOT
+            | Privilege::VFS_LINK
+            | Privilege::VFS_SETGID
+            | Privilege::VFS_STAT
+            | Privilege::VFS_STICKYFILE
+            | Privilege::NETINET_RESERVEDPORT => Ok(()),
+            _ => Err(PrivilegeError::NoPrivilege),
+        }
     }
 }
 
@@ -89,3 +179,65 @@ impl Errno for PrivilegeError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prison_priv_check() {
+        let mut cred = Ucred::new(0, AuthInfo::EXE.clone());
+
+        cred.jailed = true;
+        cred.privileges_mut().insert(Privilege::REBOOT);
+
+        assert!(cred.priv_check(Privilege::VFS_READ).is_ok());
+        assert!(matches!(
+            cred.priv_check(Privilege::REBOOT),
+            Err(PrivilegeError::NoPrivilege)
+        ));
+    }
+
+    #[test]
+    fn test_granted_privilege() {
+        let mut cred = Ucred::new(1, AuthInfo::EXE.clone());
+
+        assert!(cred.priv_check(Privilege::VFS_ADMIN).is_err());
+
+        cred.privileges_mut().insert(Privilege::VFS_ADMIN);
+
+        assert!(cred.priv_check(Privilege::VFS_ADMIN).is_ok());
+    }
+
+    #[test]
+    fn test_system_privilege() {
+        let cred = Ucred::new(1, AuthInfo::EXE.clone());
+
+        assert!(cred.is_system());
+        assert!(cred.priv_check(Privilege::MAXFILES).is_ok());
+        assert!(cred.priv_check(Privilege::PROC_SETLOGIN).is_ok());
+        assert!(cred.priv_check(Privilege::SCE680).is_ok());
+        assert!(cred.priv_check(Privilege::SCE683).is_ok());
+        assert!(cred.priv_check(Privilege::SCE686).is_ok());
+    }
+
+    #[test]
+    fn test_superuser() {
+        let root = Ucred::new(0, AuthInfo::EXE.clone());
+        let user = Ucred::new(1, AuthInfo::EXE.clone());
+
+        assert!(root.priv_check(Privilege::REBOOT).is_ok());
+        assert!(matches!(
+            user.priv_check(Privilege::REBOOT),
+            Err(PrivilegeError::NoPrivilege)
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_privilege() {
+        let cred = Ucred::new(0, AuthInfo::EXE.clone());
+
+        cred.priv_check(Privilege::from(999)).ok();
+    }
+}
@@ -1,32 +1,365 @@
 use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+use std::str::FromStr;
+use thiserror::Error;
 
 /// Privilege identifier.
 ///
 /// See https://github.com/freebsd/freebsd-src/blob/release/9.1.0/sys/sys/priv.h for standard
 /// FreeBSD privileges.
 #[repr(transparent)]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Privilege(i32);
 
 impl Privilege {
+    pub const ACCT: Self = Self(2);
     pub const MAXFILES: Self = Self(3);
+    pub const MAXPROC: Self = Self(4);
+    pub const KTRACE: Self = Self(5);
+    pub const SETDUMPER: Self = Self(6);
+    pub const REBOOT: Self = Self(8);
+    pub const SWAPON: Self = Self(9);
+    pub const SWAPOFF: Self = Self(10);
+    pub const MSGBUF: Self = Self(11);
+    pub const IO: Self = Self(12);
+    pub const KEYBOARD: Self = Self(13);
+    pub const DRIVER: Self = Self(14);
+    pub const ADJTIME: Self = Self(15);
+    pub const NTP_ADJTIME: Self = Self(16);
+    pub const CLOCK_SETTIME: Self = Self(17);
+    pub const SETTIMEOFDAY: Self = Self(18);
+    pub const CRED_SETUID: Self = Self(50);
+    pub const CRED_SETEUID: Self = Self(51);
+    pub const CRED_SETGID: Self = Self(52);
+    pub const CRED_SETEGID: Self = Self(53);
+    pub const CRED_SETGROUPS: Self = Self(54);
+    pub const CRED_SETREUID: Self = Self(55);
+    pub const CRED_SETREGID: Self = Self(56);
+    pub const CRED_SETRESUID: Self = Self(57);
+    pub const CRED_SETRESGID: Self = Self(58);
+    pub const SEEOTHERGIDS: Self = Self(59);
+    pub const SEEOTHERUIDS: Self = Self(60);
+    pub const DEBUG_DIFFCRED: Self = Self(80);
+    pub const DEBUG_SUGID: Self = Self(81);
+    pub const DEBUG_UNPRIV: Self = Self(82);
+    pub const DEBUG_DENIED: Self = Self(83);
+    pub const JAIL_ATTACH: Self = Self(110);
+    pub const JAIL_SET: Self = Self(111);
+    pub const JAIL_REMOVE: Self = Self(112);
+    pub const KENV_SET: Self = Self(120);
+    pub const KENV_UNSET: Self = Self(121);
+    pub const KLD_LOAD: Self = Self(130);
+    pub const KLD_UNLOAD: Self = Self(131);
+    pub const PROC_LIMIT: Self = Self(160);
     pub const PROC_SETLOGIN: Self = Self(161);
+    pub const PROC_SETRLIMIT: Self = Self(162);
+    pub const PROC_SETLOGINCLASS: Self = Self(163);
+    pub const IPC_READ: Self = Self(170);
+    pub const IPC_WRITE: Self = Self(171);
+    pub const IPC_ADMIN: Self = Self(172);
+    pub const IPC_MSGSIZE: Self = Self(173);
+    pub const MQ_ADMIN: Self = Self(180);
+    pub const SCHED_DIFFCRED: Self = Self(200);
+    pub const SCHED_SETPRIORITY: Self = Self(201);
+    pub const SCHED_RTPRIO: Self = Self(202);
+    pub const SCHED_SETPOLICY: Self = Self(203);
+    pub const SCHED_SET: Self = Self(204);
+    pub const SCHED_SETPARAM: Self = Self(205);
+    pub const SCHED_CPUSET: Self = Self(206);
+    pub const SCHED_CPUSET_INTR: Self = Self(207);
+    pub const SEM_WRITE: Self = Self(220);
+    pub const SIGNAL_DIFFCRED: Self = Self(230);
+    pub const SIGNAL_SUGID: Self = Self(231);
+    pub const SYSCTL_DEBUG: Self = Self(240);
+    pub const SYSCTL_WRITE: Self = Self(241);
+    pub const SYSCTL_WRITEJAIL: Self = Self(242);
+    pub const VFS_READ: Self = Self(310);
+    pub const VFS_WRITE: Self = Self(311);
     pub const VFS_ADMIN: Self = Self(312);
+    pub const VFS_EXEC: Self = Self(313);
+    pub const VFS_LOOKUP: Self = Self(314);
+    pub const VFS_BLOCKRESERVE: Self = Self(315);
+    pub const VFS_CHFLAGS_DEV: Self = Self(316);
+    pub const VFS_CHOWN: Self = Self(317);
+    pub const VFS_CHROOT: Self = Self(318);
+    pub const VFS_RETAINSUGID: Self = Self(319);
+    pub const VFS_EXCEEDQUOTA: Self = Self(320);
+    pub const VFS_EXTATTR_SYSTEM: Self = Self(321);
+    pub const VFS_FCHROOT: Self = Self(322);
+    pub const VFS_FHOPEN: Self = Self(323);
+    pub const VFS_FHSTAT: Self = Self(324);
+    pub const VFS_FHSTATFS: Self = Self(325);
+    pub const VFS_GENERATION: Self = Self(326);
+    pub const VFS_GETFH: Self = Self(327);
+    pub const VFS_GETQUOTA: Self = Self(328);
+    pub const VFS_LINK: Self = Self(329);
+    pub const VFS_MKNOD_BAD: Self = Self(330);
+    pub const VFS_MKNOD_DEV: Self = Self(331);
+    pub const VFS_MKNOD_WHT: Self = Self(332);
+    pub const VFS_MOUNT: Self = Self(333);
+    pub const VFS_MOUNT_OWNER: Self = Self(334);
+    pub const VFS_MOUNT_EXPORTED: Self = Self(335);
+    pub const VFS_MOUNT_PERM: Self = Self(336);
+    pub const VFS_MOUNT_SUIDDIR: Self = Self(337);
+    pub const VFS_MOUNT_NONUSER: Self = Self(338);
+    pub const VFS_SETGID: Self = Self(339);
+    pub const VFS_SETQUOTA: Self = Self(340);
+    pub const VFS_STICKYFILE: Self = Self(341);
+    pub const VFS_SYSFLAGS: Self = Self(342);
+    pub const VFS_UNMOUNT: Self = Self(343);
+    pub const VFS_STAT: Self = Self(344);
+    pub const VM_MADV_PROTECT: Self = Self(360);
+    pub const VM_MLOCK: Self = Self(361);
+    pub const VM_MUNLOCK: Self = Self(362);
+    pub const VM_SWAP_NOQUOTA: Self = Self(363);
+    pub const VM_SWAP_NORLIMIT: Self = Self(364);
+    pub const DEVFS_RULE: Self = Self(370);
+    pub const DEVFS_SYMLINK: Self = Self(371);
+    pub const RANDOM_RESEED: Self = Self(380);
+    pub const NETINET_RESERVEDPORT: Self = Self(490);
+    pub const NETINET_RAW: Self = Self(497);
+
+    // SCE privileges. PRIV_KMEM_READ (680) and PRIV_KMEM_WRITE (681) from the later priv.h are
+    // not defined because FreeBSD 9 does not have them and the PS4 uses these numbers for its own
+    // privileges.
     pub const SCE680: Self = Self(680);
     pub const SCE683: Self = Self(683);
     pub const SCE686: Self = Self(686);
+
+    /// Returns [`None`] if `v` is not a known privilege.
+    pub fn from_known(v: i32) -> Option<Self> {
+        let p = Self(v);
+
+        p.name().map(|_| p)
+    }
+
+    fn name(self) -> Option<&'static str> {
+        NAMES.iter().find(|v| v.0 == self).map(|v| v.1)
+    }
+}
+
+/// Names of the known privileges. [`Display`] and [`FromStr`] use this table.
+const NAMES: &[(Privilege, &str)] = &[
+    (Privilege::ACCT, "PRIV_ACCT"),
+    (Privilege::MAXFILES, "PRIV_MAXFILES"),
+    (Privilege::MAXPROC, "PRIV_MAXPROC"),
+    (Privilege::KTRACE, "PRIV_KTRACE"),
+    (Privilege::SETDUMPER, "PRIV_SETDUMPER"),
+    (Privilege::REBOOT, "PRIV_REBOOT"),
+    (Privilege::SWAPON, "PRIV_SWAPON"),
+    (Privilege::SWAPOFF, "PRIV_SWAPOFF"),
+    (Privilege::MSGBUF, "PRIV_MSGBUF"),
+    (Privilege::IO, "PRIV_IO"),
+    (Privilege::KEYBOARD, "PRIV_KEYBOARD"),
+    (Privilege::DRIVER, "PRIV_DRIVER"),
+    (Privilege::ADJTIME, "PRIV_ADJTIME"),
+    (Privilege::NTP_ADJTIME, "PRIV_NTP_ADJTIME"),
+    (Privilege::CLOCK_SETTIME, "PRIV_CLOCK_SETTIME"),
+    (Privilege::SETTIMEOFDAY, "PRIV_SETTIMEOFDAY"),
+    (Privilege::CRED_SETUID, "PRIV_CRED_SETUID"),
+    (Privilege::CRED_SETEUID, "PRIV_CRED_SETEUID"),
+    (Privilege::CRED_SETGID, "PRIV_CRED_SETGID"),
+    (Privilege::CRED_SETEGID, "PRIV_CRED_SETEGID"),
+    (Privilege::CRED_SETGROUPS, "PRIV_CRED_SETGROUPS"),
+    (Privilege::CRED_SETREUID, "PRIV_CRED_SETREUID"),
+    (Privilege::CRED_SETREGID, "PRIV_CRED_SETREGID"),
+    (Privilege::CRED_SETRESUID, "PRIV_CRED_SETRESUID"),
+    (Privilege::CRED_SETRESGID, "PRIV_CRED_SETRESGID"),
+    (Privilege::SEEOTHERGIDS, "PRIV_SEEOTHERGIDS"),
+    (Privilege::SEEOTHERUIDS, "PRIV_SEEOTHERUIDS"),
+    (Privilege::DEBUG_DIFFCRED, "PRIV_DEBUG_DIFFCRED"),
+    (Privilege::DEBUG_SUGID, "PRIV_DEBUG_SUGID"),
+    (Privilege::DEBUG_UNPRIV, "PRIV_DEBUG_UNPRIV"),
+    (Privilege::DEBUG_DENIED, "PRIV_DEBUG_DENIED"),
+    (Privilege::JAIL_ATTACH, "PRIV_JAIL_ATTACH"),
+    (Privilege::JAIL_SET, "PRIV_JAIL_SET"),
+    (Privilege::JAIL_REMOVE, "PRIV_JAIL_REMOVE"),
+    (Privilege::KENV_SET, "PRIV_KENV_SET"),
+    (Privilege::KENV_UNSET, "PRIV_KENV_UNSET"),
+    (Privilege::KLD_LOAD, "PRIV_KLD_LOAD"),
+    (Privilege::KLD_UNLOAD, "PRIV_KLD_UNLOAD"),
+    (Privilege::PROC_LIMIT, "PRIV_PROC_LIMIT"),
+    (Privilege::PROC_SETLOGIN, "PRIV_PROC_SETLOGIN"),
+    (Privilege::PROC_SETRLIMIT, "PRIV_PROC_SETRLIMIT"),
+    (Privilege::PROC_SETLOGINCLASS, "PRIV_PROC_SETLOGINCLASS"),
+    (Privilege::IPC_READ, "PRIV_IPC_READ"),
+    (Privilege::IPC_WRITE, "PRIV_IPC_WRITE"),
+    (Privilege::IPC_ADMIN, "PRIV_IPC_ADMIN"),
+    (Privilege::IPC_MSGSIZE, "PRIV_IPC_MSGSIZE"),
+    (Privilege::MQ_ADMIN, "PRIV_MQ_ADMIN"),
+    (Privilege::SCHED_DIFFCRED, "PRIV_SCHED_DIFFCRED"),
+    (Privilege::SCHED_SETPRIORITY, "PRIV_SCHED_SETPRIORITY"),
+    (Privilege::SCHED_RTPRIO, "PRIV_SCHED_RTPRIO"),
+    (Privilege::SCHED_SETPOLICY, "PRIV_SCHED_SETPOLICY"),
+    (Privilege::SCHED_SET, "PRIV_SCHED_SET"),
+    (Privilege::SCHED_SETPARAM, "PRIV_SCHED_SETPARAM"),
+    (Privilege::SCHED_CPUSET, "PRIV_SCHED_CPUSET"),
+    (Privilege::SCHED_CPUSET_INTR, "PRIV_SCHED_CPUSET_INTR"),
+    (Privilege::SEM_WRITE, "PRIV_SEM_WRITE"),
+    (Privilege::SIGNAL_DIFFCRED, "PRIV_SIGNAL_DIFFCRED"),
+    (Privilege::SIGNAL_SUGID, "PRIV_SIGNAL_SUGID"),
+    (Privilege::SYSCTL_DEBUG, "PRIV_SYSCTL_DEBUG"),
+    (Privilege::SYSCTL_WRITE, "PRIV_SYSCTL_WRITE"),
+    (Privilege::SYSCTL_WRITEJAIL, "PRIV_SYSCTL_WRITEJAIL"),
+    (Privilege::VFS_READ, "PRIV_VFS_READ"),
+    (Privilege::VFS_WRITE, "PRIV_VFS_WRITE"),
+    (Privilege::VFS_ADMIN, "PRIV_VFS_ADMIN"),
+    (Privilege::VFS_EXEC, "PRIV_VFS_EXEC"),
+    (Privilege::VFS_LOOKUP, "PRIV_VFS_LOOKUP"),
+    (Privilege::VFS_BLOCKRESERVE, "PRIV_VFS_BLOCKRESERVE"),
+    (Privilege::VFS_CHFLAGS_DEV, "PRIV_VFS_CHFLAGS_DEV"),
+    (Privilege::VFS_CHOWN, "PRIV_VFS_CHOWN"),
+    (Privilege::VFS_CHROOT, "PRIV_VFS_CHROOT"),
+    (Privilege::VFS_RETAINSUGID, "PRIV_VFS_RETAINSUGID"),
+    (Privilege::VFS_EXCEEDQUOTA, "PRIV_VFS_EXCEEDQUOTA"),
+    (Privilege::VFS_EXTATTR_SYSTEM, "PRIV_VFS_EXTATTR_SYSTEM"),
+    (Privilege::VFS_FCHROOT, "PRIV_VFS_FCHROOT"),
+    (Privilege::VFS_FHOPEN, "PRIV_VFS_FHOPEN"),
+    (Privilege::VFS_FHSTAT, "PRIV_VFS_FHSTAT"),
+    (Privilege::VFS_FHSTATFS, "PRIV_VFS_FHSTATFS"),
+    (Privilege::VFS_GENERATION, "PRIV_VFS_GENERATION"),
+    (Privilege::VFS_GETFH, "PRIV_VFS_GETFH"),
+    (Privilege::VFS_GETQUOTA, "PRIV_VFS_GETQUOTA"),
+    (Privilege::VFS_LINK, "PRIV_VFS_LINK"),
+    (Privilege::VFS_MKNOD_BAD, "PRIV_VFS_MKNOD_BAD"),
+    (Privilege::VFS_MKNOD_DEV, "PRIV_VFS_MKNOD_DEV"),
+    (Privilege::VFS_MKNOD_WHT, "PRIV_VFS_MKNOD_WHT"),
+    (Privilege::VFS_MOUNT, "PRIV_VFS_MOUNT"),
+    (Privilege::VFS_MOUNT_OWNER, "PRIV_VFS_MOUNT_OWNER"),
+    (Privilege::VFS_MOUNT_EXPORTED, "PRIV_VFS_MOUNT_EXPORTED"),
+    (Privilege::VFS_MOUNT_PERM, "PRIV_VFS_MOUNT_PERM"),
+    (Privilege::VFS_MOUNT_SUIDDIR, "PRIV_VFS_MOUNT_SUIDDIR"),
+    (Privilege::VFS_MOUNT_NONUSER, "PRIV_VFS_MOUNT_NONUSER"),
+    (Privilege::VFS_SETGID, "PRIV_VFS_SETGID"),
+    (Privilege::VFS_SETQUOTA, "PRIV_VFS_SETQUOTA"),
+    (Privilege::VFS_STICKYFILE, "PRIV_VFS_STICKYFILE"),
+    (Privilege::VFS_SYSFLAGS, "PRIV_VFS_SYSFLAGS"),
+    (Privilege::VFS_UNMOUNT, "PRIV_VFS_UNMOUNT"),
+    (Privilege::VFS_STAT, "PRIV_VFS_STAT"),
+    (Privilege::VM_MADV_PROTECT, "PRIV_VM_MADV_PROTECT"),
+    (Privilege::VM_MLOCK, "PRIV_VM_MLOCK"),
+    (Privilege::VM_MUNLOCK, "PRIV_VM_MUNLOCK"),
+    (Privilege::VM_SWAP_NOQUOTA, "PRIV_VM_SWAP_NOQUOTA"),
+    (Privilege::VM_SWAP_NORLIMIT, "PRIV_VM_SWAP_NORLIMIT"),
+    (Privilege::DEVFS_RULE, "PRIV_DEVFS_RULE"),
+    (Privilege::DEVFS_SYMLINK, "PRIV_DEVFS_SYMLINK"),
+    (Privilege::RANDOM_RESEED, "PRIV_RANDOM_RESEED"),
+    (Privilege::NETINET_RESERVEDPORT, "PRIV_NETINET_RESERVEDPORT"),
+    (Privilege::NETINET_RAW, "PRIV_NETINET_RAW"),
+    // SCE privileges.
+    (Privilege::SCE680, "SCE680"),
+    (Privilege::SCE683, "SCE683"),
+    (Privilege::SCE686, "SCE686"),
+];
+
+impl From<i32> for Privilege {
+    fn from(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Privilege> for i32 {
+    fn from(value: Privilege) -> Self {
+        value.0
+    }
 }
 
 impl Display for Privilege {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            Self::MAXFILES => f.write_str("PRIV_MAXFILES"),
-            Self::PROC_SETLOGIN => f.write_str("PRIV_PROC_SETLOGIN"),
-            Self::VFS_ADMIN => f.write_str("PRIV_VFS_ADMIN"),
-            Self::SCE680 => f.write_str("SCE680"),
-            Self::SCE683 => f.write_str("SCE683"),
-            Self::SCE686 => f.write_str("SCE686"),
-            v => v.0.fmt(f),
+        match self.name() {
+            Some(v) => f.write_str(v),
+            None => self.0.fmt(f),
+        }
+    }
+}
+
+impl FromStr for Privilege {
+    type Err = ParsePrivilegeError;
+
+    /// Accepts the same names as [`Display`] or a decimal number.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(v) = NAMES.iter().find(|v| v.1 == s) {
+            return Ok(v.0);
+        }
+
+        match s.parse() {
+            Ok(v) => Ok(Self(v)),
+            Err(e) => Err(ParsePrivilegeError(s.to_owned(), e)),
+        }
+    }
+}
+
+/// A set of [`Privilege`].
+#[derive(Debug, Clone, Default)]
+pub struct PrivilegeSet(Vec<Privilege>); // Sorted.
+
+impl PrivilegeSet {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Returns `false` if `p` is already in the set.
+    pub fn insert(&mut self, p: Privilege) -> bool {
+        match self.find(p) {
+            Ok(_) => false,
+            Err(i) => {
+                self.0.insert(i, p);
+                true
+            }
+        }
+    }
+
+    /// Returns `false` if `p` is not in the set.
+    pub fn remove(&mut self, p: Privilege) -> bool {
+        match self.find(p) {
+            Ok(i) => {
+                self.0.remove(i);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn contains(&self, p: Privilege) -> bool {
+        self.find(p).is_ok()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Privilege> + '_ {
+        self.0.iter().copied()
+    }
+
+    fn find(&self, p: Privilege) -> Result<usize, usize> {
+        self.0.binary_search(&p)
+    }
+}
+
+impl FromIterator<Privilege> for PrivilegeSet {
+    fn from_iter<T: IntoIterator<Item = Privilege>>(iter: T) -> Self {
+        let mut set = Self::new();
+
+        for p in iter {
+            set.insert(p);
+        }
+
+        set
+    }
+}
+
+impl Display for PrivilegeSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, p) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+
+            p.fmt(f)?;
         }
+
+        Ok(())
     }
 }
+
+/// Represents an error when [`Privilege`] is failed to parse from a string.
+#[derive(Debug, Error)]
+#[error("'{0}' is neither a privilege name nor a number")]
+pub struct ParsePrivilegeError(String, #[source] ParseIntError);